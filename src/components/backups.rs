@@ -0,0 +1,374 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
+use crate::tools;
+
+use super::overlay::{PrefixOverlay, OverlayMount};
+
+/// Wine prefix paths which are excluded from the backup if `exclude_caches` is set
+/// 
+/// Patterns are matched by `tar --exclude`
+pub const CACHE_PATTERNS: &[&str] = &[
+    "drive_c/users/*/Temp",
+    "drive_c/users/*/AppData/Local/Temp",
+    "drive_c/users/*/AppData/Local/D3DSCache",
+    "drive_c/users/*/AppData/Local/NVIDIA/DXCache",
+    "*.dxvk-cache",
+    "*.vkd3d-proton.cache"
+];
+
+/// Info about the wine prefix backup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupInfo {
+    /// Name of the archive file in the backups folder
+    pub archive: String,
+
+    /// Creation time in seconds since UNIX epoch
+    pub created: u64,
+
+    /// Name of the wine build used with the prefix
+    pub wine: Option<String>,
+
+    /// DXVK version installed in the prefix
+    pub dxvk: Option<String>,
+
+    /// Temp files and shader caches were not saved
+    pub exclude_caches: bool
+}
+
+impl BackupInfo {
+    #[inline]
+    pub fn created_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.created)
+    }
+}
+
+/// Wine prefix backup stored as a compressed archive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixBackup {
+    pub name: String,
+
+    /// Path to the backup's archive
+    pub archive: PathBuf,
+
+    pub info: BackupInfo
+}
+
+/// Storage of the wine prefix backups
+/// 
+/// Every backup is a `tar` archive compressed with `zstd` if it's
+/// available or with `gzip` otherwise, and a JSON file with its info
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixBackups {
+    /// Folder with the backups
+    pub folder: PathBuf,
+
+    /// Wine prefix path
+    pub prefix: PathBuf,
+
+    /// Overlay which merged view is the prefix
+    /// 
+    /// It's mounted only while the backup is created or restored
+    pub overlay: Option<PrefixOverlay>
+}
+
+impl PrefixBackups {
+    #[inline]
+    pub fn new(folder: impl Into<PathBuf>, prefix: impl Into<PathBuf>) -> Self {
+        Self {
+            folder: folder.into(),
+            prefix: prefix.into(),
+            overlay: None
+        }
+    }
+
+    #[inline]
+    /// Back up the overlay's merged view mounted to the prefix folder
+    pub fn with_overlay(self, overlay: PrefixOverlay) -> Self {
+        Self {
+            overlay: Some(overlay),
+            ..self
+        }
+    }
+
+    #[inline]
+    fn mount_overlay(&self) -> anyhow::Result<Option<OverlayMount>> {
+        self.overlay.as_ref()
+            .map(PrefixOverlay::mount)
+            .transpose()
+    }
+
+    /// List available backups, newest first
+    pub fn list(&self) -> anyhow::Result<Vec<PrefixBackup>> {
+        if !self.folder.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut backups = Vec::new();
+
+        for entry in self.folder.read_dir()?.flatten() {
+            let path = entry.path();
+
+            if path.extension().map(|ext| ext != "json").unwrap_or(true) {
+                continue;
+            }
+
+            let info = match serde_json::from_slice::<BackupInfo>(&std::fs::read(&path)?) {
+                Ok(info) => info,
+                Err(err) => {
+                    tracing::warn!("Failed to read backup info {path:?}: {err}");
+
+                    continue;
+                }
+            };
+
+            let archive = self.folder.join(&info.archive);
+
+            if !archive.exists() {
+                tracing::warn!("Backup archive doesn't exist: {archive:?}");
+
+                continue;
+            }
+
+            backups.push(PrefixBackup {
+                name: path.file_stem()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                archive,
+                info
+            });
+        }
+
+        backups.sort_by(|a, b| b.info.created.cmp(&a.info.created));
+
+        Ok(backups)
+    }
+
+    /// Create backup of the wine prefix
+    /// 
+    /// `wine` is the name of the wine build used with the prefix
+    #[tracing::instrument(level = "info", skip(self))]
+    pub fn create(&self, wine: Option<&str>, exclude_caches: bool) -> anyhow::Result<PrefixBackup> {
+        tracing::info!("Creating wine prefix backup");
+
+        let _overlay = self.mount_overlay()?;
+
+        if !self.prefix.exists() {
+            anyhow::bail!("Wine prefix doesn't exist: {:?}", self.prefix);
+        }
+
+        ensure_not_running(&self.prefix)?;
+
+        let created = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default();
+
+        let (compressor, extension) = match tools::find_binary("zstd") {
+            Some(_) => ("--zstd", "tar.zst"),
+            None => ("--gzip", "tar.gz")
+        };
+
+        // Several backups can be created within one second
+        let mut name = format!("prefix-{created}");
+        let mut suffix = 0;
+
+        while self.folder.join(format!("{name}.json")).exists()
+            || self.folder.join(format!("{name}.{extension}")).exists()
+            || self.folder.join(format!("{name}.{extension}.tmp")).exists()
+        {
+            suffix += 1;
+
+            name = format!("prefix-{created}-{suffix}");
+        }
+
+        let info = BackupInfo {
+            archive: format!("{name}.{extension}"),
+            created,
+            wine: wine.map(String::from),
+            dxvk: get_dxvk_version(&self.prefix),
+            exclude_caches
+        };
+
+        std::fs::create_dir_all(&self.folder)?;
+
+        let archive = self.folder.join(&info.archive);
+
+        // Archive is written under a temp name so interrupted
+        // backups are never listed
+        let temp = self.folder.join(format!("{}.tmp", info.archive));
+
+        let mut command = Command::new("tar");
+
+        command.arg("--create")
+            .arg(compressor)
+            .arg("--file").arg(&temp)
+            .arg("--directory").arg(&self.prefix);
+
+        #[cfg(feature = "game")]
+        command.arg(format!("--exclude=./{}", crate::launch::lock::LOCK_FILE_NAME));
+
+        if exclude_caches {
+            for pattern in CACHE_PATTERNS {
+                command.arg(format!("--exclude={pattern}"));
+            }
+        }
+
+        let output = command.arg(".").output()?;
+
+        if !output.status.success() {
+            std::fs::remove_file(&temp).ok();
+
+            anyhow::bail!("Failed to create wine prefix backup: {}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        std::fs::rename(&temp, &archive)?;
+        std::fs::write(self.folder.join(format!("{name}.json")), serde_json::to_string_pretty(&info)?)?;
+
+        Ok(PrefixBackup {
+            name,
+            archive,
+            info
+        })
+    }
+
+    /// Replace the wine prefix by the backup
+    /// 
+    /// Backup is extracted near the prefix first,
+    /// so the current prefix is kept if extraction fails
+    #[tracing::instrument(level = "info", skip(self))]
+    pub fn restore(&self, backup: &PrefixBackup) -> anyhow::Result<()> {
+        tracing::info!("Restoring wine prefix backup");
+
+        let _overlay = self.mount_overlay()?;
+
+        ensure_not_running(&self.prefix)?;
+
+        let restored = with_suffix(&self.prefix, ".restore");
+        let old = with_suffix(&self.prefix, ".old");
+
+        for folder in [&restored, &old] {
+            if folder.exists() {
+                std::fs::remove_dir_all(folder)?;
+            }
+        }
+
+        std::fs::create_dir_all(&restored)?;
+
+        // Compression format is detected by tar itself
+        let output = Command::new("tar")
+            .arg("--extract")
+            .arg("--file").arg(&backup.archive)
+            .arg("--directory").arg(&restored)
+            .output()?;
+
+        if !output.status.success() {
+            std::fs::remove_dir_all(&restored).ok();
+
+            anyhow::bail!("Failed to extract wine prefix backup: {}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        // Mount point of the overlay can't be replaced,
+        // so the merged view's content is replaced instead
+        if self.overlay.is_some() {
+            for entry in self.prefix.read_dir()?.flatten() {
+                let path = entry.path();
+
+                if path.is_dir() && !path.is_symlink() {
+                    std::fs::remove_dir_all(path)?;
+                } else {
+                    std::fs::remove_file(path)?;
+                }
+            }
+
+            let output = Command::new("cp")
+                .arg("--archive")
+                .arg("--no-target-directory")
+                .arg(&restored)
+                .arg(&self.prefix)
+                .output()?;
+
+            std::fs::remove_dir_all(&restored)?;
+
+            if !output.status.success() {
+                anyhow::bail!("Failed to copy wine prefix backup to the overlay: {}", String::from_utf8_lossy(&output.stderr));
+            }
+
+            return Ok(());
+        }
+
+        if self.prefix.exists() {
+            std::fs::rename(&self.prefix, &old)?;
+        }
+
+        std::fs::rename(&restored, &self.prefix)?;
+
+        if old.exists() {
+            std::fs::remove_dir_all(&old)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the backup's archive and info
+    pub fn remove(&self, backup: &PrefixBackup) -> anyhow::Result<()> {
+        if backup.archive.exists() {
+            std::fs::remove_file(&backup.archive)?;
+        }
+
+        let info = self.folder.join(format!("{}.json", backup.name));
+
+        if info.exists() {
+            std::fs::remove_file(info)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Get path of the folder near the given one
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_os_string();
+
+    path.push(suffix);
+
+    PathBuf::from(path)
+}
+
+/// Get DXVK version installed in the prefix
+/// 
+/// Proton stores the real prefix in the `pfx` folder
+fn get_dxvk_version(prefix: &Path) -> Option<String> {
+    let pfx = prefix.join("pfx");
+
+    let prefix = if pfx.exists() {
+        pfx.as_path()
+    } else {
+        prefix
+    };
+
+    wincompatlib::dxvk::Dxvk::get_version(prefix).ok().flatten()
+}
+
+#[cfg(feature = "game")]
+fn ensure_not_running(prefix: &Path) -> anyhow::Result<()> {
+    use crate::launch::{PrefixLock, AlreadyRunning};
+
+    if let Some(pid) = PrefixLock::holder(prefix) {
+        return Err(AlreadyRunning {
+            pid,
+            lock: PrefixLock::lock_file(prefix)
+        }.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "game"))]
+#[inline]
+fn ensure_not_running(_prefix: &Path) -> anyhow::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,275 @@
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anime_game_core::installer::downloader::Downloader;
+use anime_game_core::installer::archives::Archive;
+
+/// Name of the folder with unfinished downloads in the components folder
+pub const DOWNLOADS_FOLDER: &str = ".downloads";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DownloadProgress {
+    /// Downloaded and total bytes
+    Downloading(u64, u64),
+
+    /// Archive is being extracted
+    Extracting,
+
+    /// Component is being moved to its folder
+    Installing
+}
+
+/// Component downloading was cancelled by the progress callback
+/// 
+/// Returned by `download_component` inside `anyhow::Error`,
+/// so it can be found using `error.downcast_ref::<DownloadCancelled>()`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadCancelled {
+    /// Archive of the cancelled download is still being downloaded in background
+    /// 
+    /// Downloader can't be interrupted, so the archive is removed only when it's finished.
+    /// The same component can't be downloaded again until then
+    pub finishing: bool
+}
+
+impl std::fmt::Display for DownloadCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Component downloading was cancelled")?;
+
+        if self.finishing {
+            write!(f, ". Its archive is still being downloaded in background")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for DownloadCancelled {}
+
+/// Download component archive and install it to the `folder/name` folder
+/// 
+/// `format` is the archive format like `tar.xz` or `zip`. If it's not set,
+/// then it's taken from the `uri`. Archive is downloaded to the `.downloads`
+/// folder and extracted there, then moved to its place in one step, so
+/// `folder/name` never contains a partially installed component.
+/// 
+/// `progress` returns `false` to cancel downloading. Downloader can't be interrupted,
+/// so the cancelled download is finished in background and its archive is removed
+/// after that (see `DownloadCancelled::finishing`). Partially downloaded archive of the
+/// failed download is kept, so the next call continues downloading. Extraction can't be
+/// interrupted, so the cancellation is checked only before and after it. Does nothing
+/// if the component is already installed
+#[tracing::instrument(level = "debug", skip(progress))]
+pub fn download_component(
+    uri: &str,
+    format: Option<&str>,
+    name: &str,
+    folder: &Path,
+    progress: impl Fn(DownloadProgress) -> bool
+) -> anyhow::Result<PathBuf> {
+    let path = folder.join(name);
+
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let format = match format {
+        Some(format) => format.to_string(),
+        None => get_uri_format(uri)
+            .ok_or_else(|| anyhow::anyhow!("Failed to get archive format of {uri}"))?
+    };
+
+    if !is_supported_format(&format) {
+        anyhow::bail!("Unsupported archive format: {format}");
+    }
+
+    let downloads = folder.join(DOWNLOADS_FOLDER);
+
+    std::fs::create_dir_all(&downloads)?;
+
+    // Archive format is detected by its extension
+    let extension = match format.as_str() {
+        "tgz" => "tar.gz",
+        format => format
+    };
+
+    let archive = downloads.join(format!("{name}.{extension}"));
+    let extracted = downloads.join(name);
+
+    tracing::debug!("Downloading component archive");
+
+    download_archive(uri, &archive, &progress)?;
+
+    if !progress(DownloadProgress::Extracting) {
+        return Err(DownloadCancelled { finishing: false }.into());
+    }
+
+    tracing::debug!("Extracting component archive");
+
+    if extracted.exists() {
+        std::fs::remove_dir_all(&extracted)?;
+    }
+
+    let extracting = Archive::open(&archive)
+        .and_then(|archive| archive.extract(&extracted));
+
+    if let Err(err) = extracting {
+        tracing::error!("Extracting failed: {err}");
+
+        // Broken archive would be "continued" by the next call
+        std::fs::remove_dir_all(&extracted).ok();
+        std::fs::remove_file(&archive).ok();
+
+        return Err(err);
+    }
+
+    if !progress(DownloadProgress::Installing) {
+        std::fs::remove_dir_all(&extracted).ok();
+
+        return Err(DownloadCancelled { finishing: false }.into());
+    }
+
+    // Archives usually have one folder with the component inside
+    let mut entries = extracted.read_dir()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+
+    let root = match entries.pop() {
+        Some(entry) if entries.is_empty() && entry.is_dir() => entry,
+        _ => extracted.clone()
+    };
+
+    std::fs::rename(&root, &path)?;
+
+    if extracted.exists() {
+        std::fs::remove_dir_all(&extracted)?;
+    }
+
+    std::fs::remove_file(&archive)?;
+
+    Ok(path)
+}
+
+/// Get archive format from the uri's file name, like `tar.xz`
+fn get_uri_format(uri: &str) -> Option<String> {
+    let file_name = uri.rsplit('/').next()?;
+
+    ["tar.gz", "tar.xz", "tar.zst", "tar.bz2", "tgz", "tar", "zip"].into_iter()
+        .find(|format| file_name.ends_with(&format!(".{format}")))
+        .map(String::from)
+}
+
+#[inline]
+fn is_supported_format(format: &str) -> bool {
+    format == "zip" || format == "tgz" || format.starts_with("tar")
+}
+
+/// Archives which are being downloaded right now, and their cancellation flags
+/// 
+/// Downloads of the cancelled calls can't be stopped, so they're
+/// finished in background and can't be started again until then
+static DOWNLOADING: Mutex<Vec<(PathBuf, Arc<AtomicBool>)>> = Mutex::new(Vec::new());
+
+/// Download archive in a background thread
+/// 
+/// `progress` is called from the current thread between the downloaded chunks,
+/// and `DownloadCancelled` is returned as soon as it returns `false`
+fn download_archive(uri: &str, archive: &Path, progress: impl Fn(DownloadProgress) -> bool) -> anyhow::Result<()> {
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    {
+        let mut downloading = DOWNLOADING.lock()
+            .map_err(|err| anyhow::anyhow!("Failed to lock downloads list: {err}"))?;
+
+        if let Some((_, cancelled)) = downloading.iter().find(|(path, _)| path == archive) {
+            if cancelled.load(Ordering::Relaxed) {
+                return Err(DownloadCancelled { finishing: true }.into());
+            }
+
+            anyhow::bail!("Component archive is already being downloaded: {archive:?}");
+        }
+
+        downloading.push((archive.to_path_buf(), cancelled.clone()));
+    }
+
+    let mut downloader = match Downloader::new(uri) {
+        Ok(downloader) => downloader,
+        Err(err) => {
+            release_archive(archive);
+
+            return Err(err.into());
+        }
+    };
+
+    let (sender, receiver) = mpsc::channel();
+
+    let worker = {
+        let archive = archive.to_path_buf();
+        let cancelled = cancelled.clone();
+
+        std::thread::spawn(move || {
+            let progress_sender = sender.clone();
+            let progress_cancelled = cancelled.clone();
+
+            let result = downloader.download(&archive, move |current, total| {
+                // Nobody listens to the progress of the cancelled download
+                if !progress_cancelled.load(Ordering::Relaxed) {
+                    progress_sender.send(DownloadMessage::Progress(current, total)).ok();
+                }
+            });
+
+            // Archive of the cancelled download is not continued by the next call
+            if cancelled.load(Ordering::Relaxed) {
+                std::fs::remove_file(&archive).ok();
+            }
+
+            release_archive(&archive);
+
+            sender.send(DownloadMessage::Finished(result.map_err(anyhow::Error::from))).ok();
+        })
+    };
+
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(100)) {
+            Ok(DownloadMessage::Progress(current, total)) => {
+                if !progress(DownloadProgress::Downloading(current, total)) {
+                    cancelled.store(true, Ordering::Relaxed);
+
+                    return Err(DownloadCancelled { finishing: true }.into());
+                }
+            }
+
+            Ok(DownloadMessage::Finished(result)) => {
+                worker.join().ok();
+
+                if let Err(err) = &result {
+                    tracing::error!("Downloading failed: {err}");
+                }
+
+                return result;
+            }
+
+            Err(mpsc::RecvTimeoutError::Timeout) => (),
+
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                release_archive(archive);
+
+                anyhow::bail!("Downloading thread stopped unexpectedly");
+            }
+        }
+    }
+}
+
+enum DownloadMessage {
+    Progress(u64, u64),
+    Finished(anyhow::Result<()>)
+}
+
+fn release_archive(archive: &Path) {
+    if let Ok(mut downloading) = DOWNLOADING.lock() {
+        downloading.retain(|(path, _)| path != archive);
+    }
+}
@@ -6,6 +6,7 @@ use serde_json::Value as JsonValue;
 use wincompatlib::prelude::*;
 
 use super::loader::ComponentsLoader;
+use super::download::{download_component, DownloadProgress};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Group {
@@ -42,6 +43,10 @@ pub struct Features {
     /// - `%temp%` - path to temp folder specified in config file
     /// - `%launcher%` - path to launcher folder
     /// - `%game%` - path to the game
+    /// - `%patch%` - path to the patch folder (only for games which need it)
+    /// - `%edition%` - game edition (if the game has editions)
+    /// - `%game_version%` - installed game version
+    /// - `%wine_name%` - name of the selected wine build
     pub env: HashMap<String, String>,
 
     pub recommended: bool
@@ -98,10 +103,15 @@ pub struct Version {
 }
 
 impl Version {
-    #[inline]
     /// Get latest recommended dxvk version
+    /// 
+    /// It's the first version of the first group in the components index.
+    /// Empty groups are skipped
     pub fn latest<T: Into<PathBuf>>(components: T) -> anyhow::Result<Self> {
-        Ok(get_groups(components)?[0].versions[0].clone())
+        get_groups(components)?
+            .into_iter()
+            .find_map(|group| group.versions.into_iter().next())
+            .ok_or_else(|| anyhow::anyhow!("Components index doesn't have dxvk versions"))
     }
 
     /// Find dxvk version with given name in components index
@@ -169,6 +179,14 @@ impl Version {
         folder.into().join(&self.name).exists()
     }
 
+    /// Download dxvk and extract it to the `folder/name` folder
+    /// 
+    /// `progress` returns `false` to cancel downloading.
+    /// See `download_component` for details
+    pub fn download_to<T: Into<PathBuf>>(&self, folder: T, progress: impl Fn(DownloadProgress) -> bool) -> anyhow::Result<PathBuf> {
+        download_component(&self.uri, self.format.as_deref(), &self.name, &folder.into(), progress)
+    }
+
     #[inline]
     #[tracing::instrument(level = "debug", ret)]
     /// Install current dxvk
@@ -0,0 +1,527 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use super::wine;
+use super::dxvk;
+
+/// Latest components index schema version supported by the SDK
+/// 
+/// Indexes without `schema_version` field are considered to be of the version 1
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Components index is made for a newer SDK version
+/// 
+/// Returned by `check_compatibility` inside `anyhow::Error`,
+/// so it can be found using `error.downcast_ref::<NewerSdkRequired>()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewerSdkRequired {
+    /// Components index folder
+    pub index: PathBuf,
+
+    pub schema_version: u64,
+    pub min_sdk_version: Option<String>
+}
+
+impl std::fmt::Display for NewerSdkRequired {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Components index requires newer SDK: ")?;
+
+        match &self.min_sdk_version {
+            Some(version) if is_newer_version(version, crate::VERSION) => {
+                write!(f, "SDK version {version} or later is needed, current is {}", crate::VERSION)
+            }
+
+            _ => write!(f, "schema version {} is not supported, latest supported is {SCHEMA_VERSION}", self.schema_version)
+        }
+    }
+}
+
+impl std::error::Error for NewerSdkRequired {}
+
+/// Info stored in the `components.json` file
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct IndexInfo {
+    #[serde(default = "IndexInfo::legacy_schema_version")]
+    pub schema_version: u64,
+
+    /// Minimal SDK version which can use the index
+    #[serde(default)]
+    pub min_sdk_version: Option<String>
+}
+
+impl IndexInfo {
+    #[inline]
+    fn legacy_schema_version() -> u64 {
+        1
+    }
+
+    /// Check if the index can be used by the current SDK version
+    #[inline]
+    pub fn is_supported(&self) -> bool {
+        self.schema_version <= SCHEMA_VERSION && !self.min_sdk_version.as_ref()
+            .map(|version| is_newer_version(version, crate::VERSION))
+            .unwrap_or_default()
+    }
+}
+
+/// Problem found in the components index
+/// 
+/// Points to the file, and optionally to the group and the entry within it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexError {
+    pub file: PathBuf,
+
+    /// Name or position of the group in `components.json`
+    pub group: Option<String>,
+
+    /// Position of the entry in the group's file
+    pub entry: Option<usize>,
+
+    pub message: String
+}
+
+impl IndexError {
+    #[inline]
+    pub fn new(file: impl Into<PathBuf>, message: impl ToString) -> Self {
+        Self {
+            file: file.into(),
+            group: None,
+            entry: None,
+            message: message.to_string()
+        }
+    }
+
+    #[inline]
+    pub fn with_group(self, group: impl ToString) -> Self {
+        Self {
+            group: Some(group.to_string()),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn with_entry(self, entry: usize) -> Self {
+        Self {
+            entry: Some(entry),
+            ..self
+        }
+    }
+}
+
+impl std::fmt::Display for IndexError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.file.to_string_lossy())?;
+
+        if let Some(group) = &self.group {
+            write!(f, ", group {group}")?;
+        }
+
+        if let Some(entry) = self.entry {
+            write!(f, ", entry #{entry}")?;
+        }
+
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for IndexError {}
+
+/// Components index parsing result
+/// 
+/// Entries which failed to parse are skipped and listed in `errors`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parsed<T> {
+    pub items: Vec<T>,
+    pub errors: Vec<IndexError>
+}
+
+impl<T> Parsed<T> {
+    /// Log skipped entries and return parsed ones
+    pub fn warn(self) -> Vec<T> {
+        for error in self.errors {
+            tracing::warn!("Skipping components index entry: {error}");
+        }
+
+        self.items
+    }
+
+    pub fn map<R>(self, f: impl FnMut(T) -> R) -> Parsed<R> {
+        Parsed {
+            items: self.items.into_iter().map(f).collect(),
+            errors: self.errors
+        }
+    }
+}
+
+/// Group entry of the `components.json` file
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct IndexGroup {
+    pub name: String,
+    pub title: String,
+
+    #[serde(default)]
+    pub features: Option<JsonValue>
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WineEntry {
+    pub name: String,
+    pub title: String,
+    pub uri: String,
+
+    #[serde(default)]
+    pub format: Option<String>,
+
+    pub files: wine::Files,
+
+    #[serde(default)]
+    pub features: Option<JsonValue>
+}
+
+impl From<WineEntry> for wine::Version {
+    fn from(entry: WineEntry) -> Self {
+        Self {
+            name: entry.name,
+            title: entry.title,
+            uri: entry.uri,
+            format: entry.format,
+            files: entry.files,
+            managed: false,
+            features: entry.features.as_ref().map(wine::Features::from)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DxvkEntry {
+    pub name: String,
+    pub title: String,
+    pub version: String,
+    pub uri: String,
+
+    #[serde(default)]
+    pub format: Option<String>,
+
+    #[serde(default)]
+    pub features: Option<JsonValue>
+}
+
+impl From<DxvkEntry> for dxvk::Version {
+    fn from(entry: DxvkEntry) -> Self {
+        Self {
+            name: entry.name,
+            title: entry.title,
+            version: entry.version,
+            uri: entry.uri,
+            format: entry.format,
+            features: entry.features.as_ref().map(dxvk::Features::from)
+        }
+    }
+}
+
+/// Read JSON file of the components index
+fn read_json(path: &Path) -> Result<JsonValue, IndexError> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| IndexError::new(path, format!("failed to read file: {err}")))?;
+
+    serde_json::from_str(&content)
+        .map_err(|err| IndexError::new(path, format!("failed to parse file: {err}")))
+}
+
+/// Read info of the components index
+pub fn get_info(index: &Path) -> Result<IndexInfo, IndexError> {
+    let path = index.join("components.json");
+
+    IndexInfo::deserialize(&read_json(&path)?)
+        .map_err(|err| IndexError::new(&path, err))
+}
+
+/// Check that the components index can be used by the current SDK version
+/// 
+/// Returns `NewerSdkRequired` error if it can't, and `IndexError`
+/// if its `min_sdk_version` can't be parsed
+pub fn check_compatibility(index: &Path) -> anyhow::Result<IndexInfo> {
+    let info = get_info(index)?;
+
+    if let Some(version) = &info.min_sdk_version {
+        if parse_version(version).is_none() {
+            return Err(IndexError::new(index.join("components.json"), format!("wrong min_sdk_version format: {version}")).into());
+        }
+    }
+
+    if !info.is_supported() {
+        return Err(NewerSdkRequired {
+            index: index.to_path_buf(),
+            schema_version: info.schema_version,
+            min_sdk_version: info.min_sdk_version
+        }.into());
+    }
+
+    Ok(info)
+}
+
+/// Read the list of groups of the given components kind (`wine` or `dxvk`)
+/// 
+/// Fails only if `components.json` itself is broken
+pub fn parse_groups(index: &Path, kind: &str) -> Result<Parsed<IndexGroup>, IndexError> {
+    let path = index.join("components.json");
+
+    let components = read_json(&path)?;
+
+    let Some(groups) = components.get(kind) else {
+        return Err(IndexError::new(&path, format!("{kind} entry not found")));
+    };
+
+    let Some(groups) = groups.as_array() else {
+        return Err(IndexError::new(&path, format!("{kind} entry must be a list")));
+    };
+
+    let mut parsed = Parsed {
+        items: Vec::with_capacity(groups.len()),
+        errors: Vec::new()
+    };
+
+    for (i, group) in groups.iter().enumerate() {
+        match IndexGroup::deserialize(group) {
+            Ok(group) => parsed.items.push(group),
+
+            Err(err) => parsed.errors.push({
+                IndexError::new(&path, err).with_group(format!("#{i} of {kind}"))
+            })
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Read entries of the group's file
+/// 
+/// Fails if the file itself is broken
+pub fn parse_entries<T: for<'de> Deserialize<'de>>(index: &Path, kind: &str, group: &str) -> Result<Parsed<T>, IndexError> {
+    let path = index.join(kind).join(format!("{group}.json"));
+
+    let entries = read_json(&path)
+        .map_err(|err| err.with_group(group))?;
+
+    let Some(entries) = entries.as_array() else {
+        return Err(IndexError::new(&path, "versions must be a list").with_group(group));
+    };
+
+    let mut parsed = Parsed {
+        items: Vec::with_capacity(entries.len()),
+        errors: Vec::new()
+    };
+
+    for (i, entry) in entries.iter().enumerate() {
+        match T::deserialize(entry) {
+            Ok(entry) => parsed.items.push(entry),
+            Err(err) => parsed.errors.push(IndexError::new(&path, err).with_group(group).with_entry(i))
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Parse wine groups of the components index
+/// 
+/// Broken groups and entries are skipped and listed in the result's errors
+pub fn parse_wine_groups(index: &Path) -> Result<Parsed<wine::Group>, IndexError> {
+    let groups = parse_groups(index, "wine")?;
+
+    let mut parsed = Parsed {
+        items: Vec::with_capacity(groups.items.len()),
+        errors: groups.errors
+    };
+
+    for group in groups.items {
+        let entries = match parse_entries::<WineEntry>(index, "wine", &group.name) {
+            Ok(entries) => entries,
+            Err(err) => {
+                parsed.errors.push(err);
+
+                continue;
+            }
+        };
+
+        parsed.errors.extend(entries.errors);
+
+        parsed.items.push(wine::Group {
+            name: group.name,
+            title: group.title,
+            features: group.features.as_ref().map(wine::Features::from),
+            managed: false,
+            versions: entries.items.into_iter().map(wine::Version::from).collect()
+        });
+    }
+
+    Ok(parsed)
+}
+
+/// Parse dxvk groups of the components index
+/// 
+/// Broken groups and entries are skipped and listed in the result's errors
+pub fn parse_dxvk_groups(index: &Path) -> Result<Parsed<dxvk::Group>, IndexError> {
+    let groups = parse_groups(index, "dxvk")?;
+
+    let mut parsed = Parsed {
+        items: Vec::with_capacity(groups.items.len()),
+        errors: groups.errors
+    };
+
+    for group in groups.items {
+        let entries = match parse_entries::<DxvkEntry>(index, "dxvk", &group.name) {
+            Ok(entries) => entries,
+            Err(err) => {
+                parsed.errors.push(err);
+
+                continue;
+            }
+        };
+
+        parsed.errors.extend(entries.errors);
+
+        parsed.items.push(dxvk::Group {
+            name: group.name,
+            title: group.title,
+            features: group.features.as_ref().map(dxvk::Features::from),
+            versions: entries.items.into_iter().map(dxvk::Version::from).collect()
+        });
+    }
+
+    Ok(parsed)
+}
+
+/// Check local components index checkout for problems
+/// 
+/// Besides entries which can't be parsed, reports unsupported schema version,
+/// duplicate group and version names, missing group files and unknown `bundle` values.
+/// Fails only if `components.json` itself is broken
+pub fn lint(index: &Path) -> Result<Vec<IndexError>, IndexError> {
+    let path = index.join("components.json");
+
+    let info = get_info(index)?;
+
+    let mut errors = Vec::new();
+
+    if info.schema_version > SCHEMA_VERSION {
+        errors.push(IndexError::new(&path, format!("schema version {} is not supported, latest supported is {SCHEMA_VERSION}", info.schema_version)));
+    }
+
+    if let Some(version) = &info.min_sdk_version {
+        if parse_version(version).is_none() {
+            errors.push(IndexError::new(&path, format!("wrong min_sdk_version format: {version}")));
+        }
+    }
+
+    for kind in ["wine", "dxvk"] {
+        let groups = match parse_groups(index, kind) {
+            Ok(groups) => groups,
+            Err(err) => {
+                errors.push(err);
+
+                continue;
+            }
+        };
+
+        errors.extend(groups.errors);
+
+        let mut group_names = HashSet::new();
+        let mut version_names = HashSet::new();
+
+        for group in groups.items {
+            if !group_names.insert(group.name.clone()) {
+                errors.push(IndexError::new(&path, format!("duplicate {kind} group name")).with_group(&group.name));
+
+                // Both groups use the same file
+                continue;
+            }
+
+            if kind == "wine" {
+                if let Some(error) = lint_bundle(group.features.as_ref()) {
+                    errors.push(IndexError::new(&path, error).with_group(&group.name));
+                }
+            }
+
+            let group_path = index.join(kind).join(format!("{}.json", group.name));
+
+            if !group_path.exists() {
+                errors.push(IndexError::new(&group_path, "group file not found").with_group(&group.name));
+
+                continue;
+            }
+
+            let entries = match kind {
+                "wine" => parse_entries::<WineEntry>(index, kind, &group.name)
+                    .map(|parsed| parsed.map(|entry| (entry.name, entry.features))),
+
+                _ => parse_entries::<DxvkEntry>(index, kind, &group.name)
+                    .map(|parsed| parsed.map(|entry| (entry.name, entry.features)))
+            };
+
+            let entries = match entries {
+                Ok(entries) => entries,
+                Err(err) => {
+                    errors.push(err);
+
+                    continue;
+                }
+            };
+
+            errors.extend(entries.errors);
+
+            for (i, (name, features)) in entries.items.into_iter().enumerate() {
+                if !version_names.insert(name.clone()) {
+                    errors.push(IndexError::new(&group_path, format!("duplicate {kind} version name: {name}")).with_group(&group.name).with_entry(i));
+                }
+
+                if kind == "wine" {
+                    if let Some(error) = lint_bundle(features.as_ref()) {
+                        errors.push(IndexError::new(&group_path, error).with_group(&group.name).with_entry(i));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(errors)
+}
+
+/// Check that `bundle` feature, if set, has a known value
+fn lint_bundle(features: Option<&JsonValue>) -> Option<String> {
+    let bundle = features?.get("bundle")?;
+
+    if bundle.is_null() || wine::Bundle::deserialize(bundle).is_ok() {
+        return None;
+    }
+
+    Some(format!("unknown bundle value: {bundle}"))
+}
+
+/// Parse numeric components of the `x.y.z` version
+/// 
+/// Trailing zeros are removed so `1.2` and `1.2.0` are equal
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let mut version = version.trim()
+        .split('.')
+        .map(|part| part.parse().ok())
+        .collect::<Option<Vec<u64>>>()?;
+
+    while version.last() == Some(&0) {
+        version.pop();
+    }
+
+    Some(version)
+}
+
+/// Check if `version` is newer than `current`
+/// 
+/// Versions which can't be parsed are not considered newer
+fn is_newer_version(version: &str, current: &str) -> bool {
+    match (parse_version(version), parse_version(current)) {
+        (Some(version), Some(current)) => version > current,
+        _ => false
+    }
+}
@@ -4,6 +4,7 @@ use crate::anime_game_core::traits::git_sync::RemoteGitSyncExt;
 
 use super::wine;
 use super::dxvk;
+use super::index as components_index;
 
 use crate::integrations::steam;
 
@@ -15,152 +16,37 @@ pub fn get_local_proton_versions(index: &Path) -> anyhow::Result<Vec<wine::Group
 }
 
 /// Try to get wine versions from components index
+/// 
+/// Fails with `NewerSdkRequired` error if the index is not supported.
+/// Broken groups and versions are skipped with a warning
 #[tracing::instrument(level = "debug")]
 #[cached::proc_macro::cached(key = "PathBuf", convert = r##"{ index.to_path_buf() }"##, result)]
 pub fn get_wine_versions(index: &Path) -> anyhow::Result<Vec<wine::Group>> {
     tracing::debug!("Getting wine versions");
 
-    let components = serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(index.join("components.json"))?)?;
-
-    match components.get("wine") {
-        Some(wine) => match wine.as_array() {
-            Some(groups) => {
-                let mut wine_groups = Vec::with_capacity(groups.len());
-
-                for group in groups {
-                    let name = match group.get("name") {
-                        Some(name) => match name.as_str() {
-                            Some(name) => name.to_string(),
-                            None => anyhow::bail!("Wrong components index structure: wine group's name entry must be a string")
-                        }
-
-                        None => anyhow::bail!("Wrong components index structure: wine group's name not found")
-                    };
-
-                    let title = match group.get("title") {
-                        Some(title) => match title.as_str() {
-                            Some(title) => title.to_string(),
-                            None => anyhow::bail!("Wrong components index structure: wine group's title entry must be a string")
-                        }
-
-                        None => anyhow::bail!("Wrong components index structure: wine group's title not found")
-                    };
-
-                    let versions = serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(index.join("wine").join(format!("{name}.json")))?)?;
-
-                    let mut wine_versions = Vec::new();
-
-                    match versions.as_array() {
-                        Some(versions) => {
-                            for version in versions {
-                                wine_versions.push(wine::Version {
-                                    name: version["name"].as_str().unwrap().to_string(),
-                                    title: version["title"].as_str().unwrap().to_string(),
-                                    uri: version["uri"].as_str().unwrap().to_string(),
-                                    format: version["format"].as_str().map(|str| str.to_string()),
-                                    files: serde_json::from_value::<wine::Files>(version["files"].to_owned())?,
-                                    managed: false,
-                                    features: version.get("features").map(|v| v.into())
-                                });
-                            }
-                        }
-
-                        None => anyhow::bail!("Wrong components index structure: wine versions must be a list")
-                    }
-
-                    wine_groups.push(wine::Group {
-                        name,
-                        title,
-                        features: group.get("features").map(|v| v.into()),
-                        managed: false,
-                        versions: wine_versions
-                    });
-                }
-
-                Ok(wine_groups)
-            }
+    components_index::check_compatibility(index)?;
 
-            None => anyhow::bail!("Wrong components index structure: wine entry must be a list")
-        }
-
-        None => anyhow::bail!("Wrong components index structure: wine entry not found")
-    }
+    Ok(components_index::parse_wine_groups(index)?.warn())
 }
 
 /// Try to get dxvk versions from components index
+/// 
+/// Fails with `NewerSdkRequired` error if the index is not supported.
+/// Broken groups and versions are skipped with a warning
 #[tracing::instrument(level = "debug")]
 #[cached::proc_macro::cached(key = "PathBuf", convert = r##"{ index.to_path_buf() }"##, result)]
 pub fn get_dxvk_versions(index: &Path) -> anyhow::Result<Vec<dxvk::Group>> {
     tracing::debug!("Getting dxvk versions");
 
-    let components = serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(index.join("components.json"))?)?;
-
-    match components.get("dxvk") {
-        Some(dxvk) => match dxvk.as_array() {
-            Some(groups) => {
-                let mut dxvk_groups = Vec::with_capacity(groups.len());
-
-                for group in groups {
-                    let name = match group.get("name") {
-                        Some(name) => match name.as_str() {
-                            Some(name) => name.to_string(),
-                            None => anyhow::bail!("Wrong components index structure: dxvk group's name entry must be a string")
-                        }
-
-                        None => anyhow::bail!("Wrong components index structure: dxvk group's name not found")
-                    };
-
-                    let title = match group.get("title") {
-                        Some(title) => match title.as_str() {
-                            Some(title) => title.to_string(),
-                            None => anyhow::bail!("Wrong components index structure: dxvk group's title entry must be a string")
-                        }
-
-                        None => anyhow::bail!("Wrong components index structure: dxvk group's title not found")
-                    };
-
-                    let versions = serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(index.join("dxvk").join(format!("{name}.json")))?)?;
-
-                    let mut dxvk_versions = Vec::new();
-
-                    match versions.as_array() {
-                        Some(versions) => {
-                            for version in versions {
-                                dxvk_versions.push(dxvk::Version {
-                                    name: version["name"].as_str().unwrap().to_string(),
-                                    title: version["title"].as_str().unwrap().to_string(),
-                                    version: version["version"].as_str().unwrap().to_string(),
-                                    uri: version["uri"].as_str().unwrap().to_string(),
-                                    format: version["format"].as_str().map(|str| str.to_string()),
-                                    features: version.get("features").map(|v| v.into())
-                                });
-                            }
-                        }
-
-                        None => anyhow::bail!("Wrong components index structure: wine versions must be a list")
-                    }
-
-                    dxvk_groups.push(dxvk::Group {
-                        name,
-                        title,
-                        features: group.get("features").map(|v| v.into()),
-                        versions: dxvk_versions
-                    });
-                }
+    components_index::check_compatibility(index)?;
 
-                Ok(dxvk_groups)
-            }
-
-            None => anyhow::bail!("Wrong components index structure: wine entry must be a list")
-        }
-
-        None => anyhow::bail!("Wrong components index structure: wine entry not found")
-    }
+    Ok(components_index::parse_dxvk_groups(index)?.warn())
 }
 
 #[derive(Debug)]
 pub struct ComponentsLoader {
-    folder: PathBuf
+    folder: PathBuf,
+    custom: Vec<wine::CustomWine>
 }
 
 impl RemoteGitSyncExt for ComponentsLoader {
@@ -174,19 +60,113 @@ impl ComponentsLoader {
     #[inline]
     pub fn new<T: Into<PathBuf>>(folder: T) -> Self {
         Self {
-            folder: folder.into()
+            folder: folder.into(),
+            custom: Vec::new()
         }
     }
 
     #[inline]
+    /// Add custom wine builds to the loaded wine versions
+    pub fn with_custom(self, custom: impl IntoIterator<Item = wine::CustomWine>) -> Self {
+        Self {
+            custom: custom.into_iter().collect(),
+            ..self
+        }
+    }
+
+    #[inline]
+    /// Check that the components index can be used by the current SDK version
+    /// 
+    /// Returns `NewerSdkRequired` error if it can't
+    pub fn check_compatibility(&self) -> anyhow::Result<components_index::IndexInfo> {
+        components_index::check_compatibility(&self.folder)
+    }
+
+    #[inline]
+    /// Check local components index checkout for problems
+    pub fn lint(&self) -> anyhow::Result<Vec<components_index::IndexError>> {
+        Ok(components_index::lint(&self.folder)?)
+    }
+
     #[tracing::instrument(level = "debug")]
     /// Try to get wine versions from components index
+    /// 
+    /// System wine and custom builds are added as the managed `custom` group.
+    /// Custom builds with names already used by the index are skipped
     pub fn get_wine_versions(&self) -> anyhow::Result<Vec<wine::Group>> {
         // TODO: seems like the right spot to hijack the versions and inject the steam environment.
-        match steam::launched_from() {
+        let mut groups = match steam::launched_from() {
             steam::LaunchedFrom::Steam => get_local_proton_versions(&self.folder),
             steam::LaunchedFrom::Independent => get_wine_versions(&self.folder)
+        }?;
+
+        if let Some(mut custom) = wine::custom::get_custom_group(&self.custom) {
+            custom.versions.retain(|version| {
+                let exists = groups.iter()
+                    .any(|group| group.versions.iter().any(|index_version| index_version.name == version.name));
+
+                if exists {
+                    tracing::warn!("Skipping custom wine build {:?}: name is already used by the components index", version.name);
+                }
+
+                !exists
+            });
+
+            if !custom.versions.is_empty() {
+                groups.push(custom);
+            }
         }
+
+        Ok(groups)
+    }
+
+    /// Find wine group with given name or with the version of given name
+    /// 
+    /// Custom builds are found in the `custom` group
+    pub fn find_wine_group(&self, name: impl AsRef<str>) -> anyhow::Result<Option<wine::Group>> {
+        let name = name.as_ref();
+
+        for group in self.get_wine_versions()? {
+            if group.name == name || group.versions.iter().any(|version| version.name == name) {
+                return Ok(Some(group));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Find wine version with given name, including custom builds
+    pub fn find_wine_version(&self, name: impl AsRef<str>) -> anyhow::Result<Option<wine::Version>> {
+        let name = name.as_ref();
+
+        for group in self.get_wine_versions()? {
+            if let Some(version) = group.versions.into_iter().find(|version| version.name == name) {
+                return Ok(Some(version));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// List wine versions downloaded in the given folder
+    /// 
+    /// Managed groups, including custom builds, are always listed
+    pub fn get_downloaded_wine_versions(&self, folder: impl AsRef<Path>) -> anyhow::Result<Vec<wine::Group>> {
+        let folder = folder.as_ref();
+
+        let mut downloaded = Vec::new();
+
+        for mut group in self.get_wine_versions()? {
+            if !group.managed {
+                group.versions.retain(|version| folder.join(&version.name).exists());
+            }
+
+            if !group.versions.is_empty() {
+                downloaded.push(group);
+            }
+        }
+
+        Ok(downloaded)
     }
 
     #[inline]
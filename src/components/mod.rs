@@ -1,3 +1,15 @@
 pub mod loader;
+pub mod index;
+pub mod download;
 pub mod wine;
 pub mod dxvk;
+pub mod overlay;
+
+#[cfg(feature = "config")]
+pub mod prefix;
+
+#[cfg(feature = "config")]
+pub mod backups;
+
+#[cfg(feature = "config")]
+pub mod templates;
@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::tools::Tool;
+
+/// Wine prefix made of a shared base prefix and per-game changes on top of it
+/// 
+/// The merged view is mounted using `fuse-overlayfs`,
+/// or by `bwrap` itself when the game is sandboxed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixOverlay {
+    /// Shared base prefix. It's never changed by the overlay
+    pub base: PathBuf,
+
+    /// Folder with the game's changes
+    pub upper: PathBuf,
+
+    /// Working folder of the overlay filesystem
+    pub work: PathBuf,
+
+    /// Mount point of the merged view
+    pub merged: PathBuf
+}
+
+impl PrefixOverlay {
+    /// Create overlay mounted to the `merged` folder
+    /// 
+    /// Changes are stored in the `<merged>.overlay` folder
+    pub fn new(base: impl Into<PathBuf>, merged: impl Into<PathBuf>) -> Self {
+        let merged = merged.into();
+
+        let mut data = merged.as_os_str().to_os_string();
+
+        data.push(".overlay");
+
+        let data = PathBuf::from(data);
+
+        Self {
+            base: base.into(),
+            upper: data.join("upper"),
+            work: data.join("work"),
+            merged
+        }
+    }
+
+    /// Check if the merged view is mounted
+    pub fn is_mounted(&self) -> bool {
+        let Ok(mounts) = std::fs::read_to_string("/proc/self/mounts") else {
+            return false;
+        };
+
+        let merged = self.merged.to_string_lossy();
+
+        mounts.lines()
+            .filter_map(|mount| mount.split(' ').nth(1))
+            .any(|mount_point| unescape_mount_point(mount_point) == merged)
+    }
+
+    /// Create folders of the overlay
+    /// 
+    /// Fails if the base prefix doesn't exist
+    pub fn create_folders(&self) -> anyhow::Result<()> {
+        if !self.base.exists() {
+            anyhow::bail!("Base wine prefix doesn't exist: {:?}", self.base);
+        }
+
+        for folder in [&self.upper, &self.work, &self.merged] {
+            std::fs::create_dir_all(folder)?;
+        }
+
+        Ok(())
+    }
+
+    /// Mount the merged view using `fuse-overlayfs`
+    /// 
+    /// The view is unmounted when the returned guard is dropped.
+    /// If it's already mounted, then it's kept mounted
+    #[tracing::instrument(level = "debug")]
+    pub fn mount(&self) -> anyhow::Result<OverlayMount> {
+        if self.is_mounted() {
+            return Ok(OverlayMount { overlay: None });
+        }
+
+        let Some(fuse_overlayfs) = Tool::FuseOverlayfs.path() else {
+            anyhow::bail!("fuse-overlayfs is not installed");
+        };
+
+        self.create_folders()?;
+
+        let output = Command::new(fuse_overlayfs)
+            .arg("-o")
+            .arg(format!(
+                "lowerdir={},upperdir={},workdir={}",
+                escape_option(&self.base),
+                escape_option(&self.upper),
+                escape_option(&self.work)
+            ))
+            .arg(&self.merged)
+            .output()?;
+
+        if !output.status.success() {
+            anyhow::bail!("Failed to mount wine prefix overlay: {}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        Ok(OverlayMount {
+            overlay: Some(self.clone())
+        })
+    }
+
+    /// Unmount the merged view
+    /// 
+    /// Does nothing if it's not mounted
+    #[tracing::instrument(level = "debug")]
+    pub fn unmount(&self) -> anyhow::Result<()> {
+        if !self.is_mounted() {
+            return Ok(());
+        }
+
+        for binary in ["fusermount3", "fusermount"] {
+            let Some(fusermount) = crate::tools::find_binary(binary) else {
+                continue;
+            };
+
+            let output = Command::new(fusermount)
+                .arg("-u")
+                .arg(&self.merged)
+                .output()?;
+
+            if !output.status.success() {
+                anyhow::bail!("Failed to unmount wine prefix overlay: {}", String::from_utf8_lossy(&output.stderr));
+            }
+
+            return Ok(());
+        }
+
+        anyhow::bail!("fusermount is not installed")
+    }
+
+    /// Get `bwrap` arguments to mount the merged view to the given folder
+    /// 
+    /// Overlay's folders must be created before running `bwrap`.
+    /// Should not be used while the overlay is mounted by `fuse-overlayfs`,
+    /// because two overlays can't share the same upper folder
+    pub fn bwrap_args(&self, dest: impl AsRef<Path>) -> Vec<String> {
+        vec![
+            String::from("--overlay-src"),
+            self.base.to_string_lossy().to_string(),
+            String::from("--overlay"),
+            self.upper.to_string_lossy().to_string(),
+            self.work.to_string_lossy().to_string(),
+            dest.as_ref().to_string_lossy().to_string()
+        ]
+    }
+
+    /// Turn the merged view into a standalone prefix
+    /// 
+    /// Merged view is copied to the `merged` folder and the game's changes
+    /// are removed. The overlay should be disabled in the config after that
+    #[tracing::instrument(level = "info")]
+    pub fn flatten(&self) -> anyhow::Result<()> {
+        tracing::info!("Flattening wine prefix overlay");
+
+        let _mount = self.mount()?;
+
+        #[cfg(feature = "game")]
+        if let Some(pid) = crate::launch::PrefixLock::holder(&self.merged) {
+            return Err(crate::launch::AlreadyRunning {
+                pid,
+                lock: crate::launch::PrefixLock::lock_file(&self.merged)
+            }.into());
+        }
+
+        let mut flattened = self.merged.as_os_str().to_os_string();
+
+        flattened.push(".flatten");
+
+        let flattened = PathBuf::from(flattened);
+
+        if flattened.exists() {
+            std::fs::remove_dir_all(&flattened)?;
+        }
+
+        let output = Command::new("cp")
+            .arg("--archive")
+            .arg("--reflink=auto")
+            .arg(&self.merged)
+            .arg(&flattened)
+            .output()?;
+
+        if !output.status.success() {
+            std::fs::remove_dir_all(&flattened).ok();
+
+            anyhow::bail!("Failed to copy wine prefix overlay: {}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        self.unmount()?;
+
+        // Mount point is an empty folder now
+        std::fs::remove_dir(&self.merged)?;
+        std::fs::rename(&flattened, &self.merged)?;
+
+        if let Some(data) = self.upper.parent() {
+            std::fs::remove_dir_all(data)?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the game's changes, so the merged view is the same as the base prefix
+    /// 
+    /// The view is unmounted first, so the overlay must not be used by anything
+    #[tracing::instrument(level = "info")]
+    pub fn remove_changes(&self) -> anyhow::Result<()> {
+        tracing::info!("Removing wine prefix overlay changes");
+
+        self.unmount()?;
+
+        if let Some(data) = self.upper.parent() {
+            if data.exists() {
+                std::fs::remove_dir_all(data)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Mounted merged view of the prefix overlay
+/// 
+/// The view is unmounted when this struct is dropped,
+/// unless it was already mounted by someone else
+#[must_use]
+#[derive(Debug)]
+pub struct OverlayMount {
+    overlay: Option<PrefixOverlay>
+}
+
+impl Drop for OverlayMount {
+    fn drop(&mut self) {
+        if let Some(overlay) = &self.overlay {
+            if let Err(err) = overlay.unmount() {
+                tracing::warn!("Failed to unmount wine prefix overlay {:?}: {err}", overlay.merged);
+            }
+        }
+    }
+}
+
+/// Mount points in `/proc/self/mounts` have spaces and tabs escaped
+fn unescape_mount_point(mount_point: &str) -> String {
+    mount_point.replace("\\040", " ")
+        .replace("\\011", "\t")
+        .replace("\\012", "\n")
+        .replace("\\134", "\\")
+}
+
+/// Commas and colons separate mount options and lower folders
+fn escape_option(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(':', "\\:")
+}
@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use wincompatlib::prelude::*;
+
+use crate::config::schema_blanks::prelude::{WineDrives, AllowedDrives};
+
+use super::wine::UnifiedWine;
+use super::templates;
+use super::overlay::{PrefixOverlay, OverlayMount};
+
+/// Name of the file with the name of the wine build
+/// which last initialized or updated the prefix
+pub const RUNNER_FILE: &str = ".launcher-runner";
+
+/// Fonts which are usually needed by the games
+pub const COREFONTS: &[Font] = &[
+    Font::Andale,
+    Font::Arial,
+    Font::Courier,
+    Font::Georgia,
+    Font::Impact,
+    Font::Times,
+    Font::Trebuchet,
+    Font::Verdana,
+    Font::Webdings,
+    Font::ComicSans
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrefixStep {
+    /// Wine prefix is being created using `wineboot`
+    Init,
+
+    /// Wine prefix is being updated using `wineboot`
+    Update,
+
+    /// Wine prefix is being cloned from the template
+    CloneTemplate,
+
+    InstallDxvk,
+    InstallFont(Font),
+
+    /// Name of the DLL
+    SetOverride(String),
+
+    MapDrives
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixProgress {
+    pub step: PrefixStep,
+
+    /// Number of the current step, starting from 1
+    pub current: usize,
+
+    pub total: usize
+}
+
+/// Wine prefix creation and provisioning
+#[derive(Debug, Clone)]
+pub struct PrefixManager {
+    /// Wine used to create the prefix
+    pub wine: UnifiedWine,
+
+    /// Wine prefix path
+    /// 
+    /// For proton builds this is the compatdata folder,
+    /// with the real prefix in its `pfx` subfolder
+    pub prefix: PathBuf,
+
+    /// Path to the DXVK build which should be installed
+    pub dxvk: Option<PathBuf>,
+
+    /// Version of the DXVK build, like `2.3`
+    /// 
+    /// Used to check if the template has the same DXVK installed
+    pub dxvk_version: Option<String>,
+
+    pub dxvk_params: InstallParams,
+
+    /// Fonts installed if they're missing
+    pub fonts: Vec<Font>,
+
+    /// DLL overrides set in the prefix
+    pub overrides: HashMap<String, Vec<OverrideMode>>,
+
+    /// Folders mapped to the prefix drives
+    pub drives: HashMap<AllowedDrives, PathBuf>,
+
+    /// Template prefix cloned instead of running `wineboot`
+    /// 
+    /// DXVK and fonts are not installed again if the template has them
+    pub template: Option<PathBuf>,
+
+    /// Name of the wine build recorded in the prefix
+    /// after its initialization or update
+    pub runner: Option<String>,
+
+    /// Overlay which merged view is the prefix
+    /// 
+    /// It's mounted only while the prefix is changed
+    pub overlay: Option<PrefixOverlay>
+}
+
+impl PrefixManager {
+    #[inline]
+    pub fn new(wine: UnifiedWine, prefix: impl Into<PathBuf>) -> Self {
+        let prefix = prefix.into();
+
+        Self {
+            wine: wine.with_prefix(&prefix),
+            prefix,
+            dxvk: None,
+            dxvk_version: None,
+            dxvk_params: InstallParams::default(),
+            fonts: Vec::new(),
+            overrides: HashMap::new(),
+            drives: HashMap::new(),
+            template: None,
+            runner: None,
+            overlay: None
+        }
+    }
+
+    #[inline]
+    pub fn with_dxvk(self, dxvk: impl Into<PathBuf>, params: InstallParams) -> Self {
+        Self {
+            dxvk: Some(dxvk.into()),
+            dxvk_params: params,
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn with_dxvk_version(self, version: impl ToString) -> Self {
+        Self {
+            dxvk_version: Some(version.to_string()),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn with_fonts(self, fonts: impl IntoIterator<Item = Font>) -> Self {
+        Self {
+            fonts: fonts.into_iter().collect(),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn with_override(mut self, dll_name: impl ToString, modes: impl IntoIterator<Item = OverrideMode>) -> Self {
+        self.overrides.insert(dll_name.to_string(), modes.into_iter().collect());
+
+        self
+    }
+
+    #[inline]
+    /// Map folders to the prefix drives according to the given config
+    pub fn with_drives(self, drives: &WineDrives, game_folder: impl Into<PathBuf>) -> Self {
+        Self {
+            drives: drives.get_drives(game_folder, self.real_prefix()),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn with_template(self, template: impl Into<PathBuf>) -> Self {
+        Self {
+            template: Some(template.into()),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn with_runner(self, runner: impl ToString) -> Self {
+        Self {
+            runner: Some(runner.to_string()),
+            ..self
+        }
+    }
+
+    #[inline]
+    /// Change the overlay's merged view mounted to the prefix folder
+    pub fn with_overlay(self, overlay: PrefixOverlay) -> Self {
+        Self {
+            overlay: Some(overlay),
+            ..self
+        }
+    }
+
+    #[inline]
+    /// Get path to the real wine prefix
+    /// 
+    /// Differs from `prefix` for proton builds
+    pub fn real_prefix(&self) -> PathBuf {
+        Self::get_real_prefix(&self.wine)
+    }
+
+    fn get_real_prefix(wine: &UnifiedWine) -> PathBuf {
+        match wine {
+            UnifiedWine::Default(wine) => wine.prefix.clone(),
+            UnifiedWine::Proton(proton) => proton.wine().prefix.clone()
+        }
+    }
+
+    /// Check if DXVK should be installed after cloning the template
+    /// 
+    /// It's installed if the template doesn't have DXVK or has another version of it.
+    /// If `dxvk_version` is not set, then any installed DXVK version is accepted
+    fn template_needs_dxvk(&self, template: &Path) -> anyhow::Result<bool> {
+        if self.dxvk.is_none() {
+            return Ok(false);
+        }
+
+        let template = Self::get_real_prefix(&self.wine.clone().with_prefix(template));
+
+        let needs_dxvk = match (Dxvk::get_version(template)?, &self.dxvk_version) {
+            (Some(installed), Some(wanted)) => &installed != wanted,
+            (installed, None) => installed.is_none(),
+            (None, _) => true
+        };
+
+        Ok(needs_dxvk)
+    }
+
+    /// Check if the wine prefix exists
+    /// 
+    /// Overlay's merged view has everything its base prefix has,
+    /// so the base prefix is checked while the overlay is not mounted
+    pub fn exists(&self) -> bool {
+        let prefix = match &self.overlay {
+            Some(overlay) if !overlay.is_mounted() => Self::get_real_prefix(&self.wine.clone().with_prefix(&overlay.base)),
+            _ => self.real_prefix()
+        };
+
+        prefix.join("drive_c").exists()
+    }
+
+    #[inline]
+    fn mount_overlay(&self) -> anyhow::Result<Option<OverlayMount>> {
+        self.overlay.as_ref()
+            .map(PrefixOverlay::mount)
+            .transpose()
+    }
+
+    /// Create wine prefix and provision it
+    /// 
+    /// Prefix is cloned from the template if it's set
+    #[tracing::instrument(level = "info", skip(updater))]
+    pub fn create(&self, updater: impl Fn(PrefixProgress)) -> anyhow::Result<()> {
+        tracing::info!("Creating wine prefix");
+
+        match &self.template {
+            Some(_) => self.run(PrefixStep::CloneTemplate, updater),
+            None => self.run(PrefixStep::Init, updater)
+        }
+    }
+
+    /// Update wine prefix and provision it again
+    /// 
+    /// Only missing fonts are installed
+    #[tracing::instrument(level = "info", skip(updater))]
+    pub fn update(&self, updater: impl Fn(PrefixProgress)) -> anyhow::Result<()> {
+        tracing::info!("Updating wine prefix");
+
+        if !self.exists() {
+            anyhow::bail!("Wine prefix doesn't exist: {:?}", self.real_prefix());
+        }
+
+        self.run(PrefixStep::Update, updater)
+    }
+
+    /// Stop wine processes and remove the prefix folder
+    /// 
+    /// If the prefix is the overlay's merged view, then only the overlay's
+    /// changes are removed and the shared base prefix is kept
+    #[tracing::instrument(level = "info")]
+    pub fn delete(&self) -> anyhow::Result<()> {
+        tracing::info!("Deleting wine prefix");
+
+        let _overlay = self.mount_overlay()?;
+
+        #[cfg(feature = "game")]
+        if let Some(pid) = crate::launch::PrefixLock::holder(&self.prefix) {
+            return Err(crate::launch::AlreadyRunning {
+                pid,
+                lock: crate::launch::PrefixLock::lock_file(&self.prefix)
+            }.into());
+        }
+
+        if let Some(overlay) = &self.overlay {
+            if let Err(err) = self.wine.stop_processes(true) {
+                tracing::warn!("Failed to stop wine processes: {err}");
+            }
+
+            return overlay.remove_changes();
+        }
+
+        if !self.prefix.exists() {
+            return Ok(());
+        }
+
+        if self.exists() {
+            if let Err(err) = self.wine.stop_processes(true) {
+                tracing::warn!("Failed to stop wine processes: {err}");
+            }
+        }
+
+        std::fs::remove_dir_all(&self.prefix)?;
+
+        Ok(())
+    }
+
+    fn run(&self, first: PrefixStep, updater: impl Fn(PrefixProgress)) -> anyhow::Result<()> {
+        let _overlay = self.mount_overlay()?;
+
+        // Cloned prefix will have everything the template has
+        let (prefix, needs_dxvk) = match (&first, &self.template) {
+            (PrefixStep::CloneTemplate, Some(template)) => (
+                Self::get_real_prefix(&self.wine.clone().with_prefix(template)),
+                self.template_needs_dxvk(template)?
+            ),
+
+            _ => (self.real_prefix(), self.dxvk.is_some())
+        };
+
+        let mut steps = vec![first];
+
+        if needs_dxvk {
+            steps.push(PrefixStep::InstallDxvk);
+        }
+
+        // Prefix doesn't exist before the init step so all the fonts are missing
+
+        steps.extend(self.fonts.iter()
+            .filter(|font| !font.is_installed(&prefix))
+            .copied()
+            .map(PrefixStep::InstallFont));
+
+        let mut overrides = self.overrides.keys().cloned().collect::<Vec<_>>();
+
+        overrides.sort();
+
+        steps.extend(overrides.into_iter().map(PrefixStep::SetOverride));
+
+        if !self.drives.is_empty() {
+            steps.push(PrefixStep::MapDrives);
+        }
+
+        let total = steps.len();
+
+        for (i, step) in steps.into_iter().enumerate() {
+            updater(PrefixProgress {
+                step: step.clone(),
+                current: i + 1,
+                total
+            });
+
+            self.run_step(step)?;
+        }
+
+        if let Some(runner) = &self.runner {
+            set_prefix_runner(&self.prefix, runner)?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
+    fn run_step(&self, step: PrefixStep) -> anyhow::Result<()> {
+        match step {
+            PrefixStep::Init => {
+                self.wine.init_prefix(Some(&self.prefix))?;
+            }
+
+            PrefixStep::Update => {
+                self.wine.update_prefix(Some(&self.prefix))?;
+            }
+
+            PrefixStep::CloneTemplate => {
+                if let Some(template) = &self.template {
+                    let method = templates::clone_prefix(template, &self.prefix)?;
+
+                    tracing::debug!("Wine prefix template cloned using {method:?}");
+                }
+            }
+
+            PrefixStep::InstallDxvk => {
+                if let Some(dxvk) = &self.dxvk {
+                    Dxvk::install(&Wine::from(self.wine.clone()), dxvk, self.dxvk_params)?;
+                }
+            }
+
+            PrefixStep::InstallFont(font) => self.wine.install_font(font)?,
+
+            PrefixStep::SetOverride(dll_name) => {
+                if let Some(modes) = self.overrides.get(&dll_name) {
+                    self.wine.add_override(&dll_name, modes.iter().copied())?;
+                }
+            }
+
+            PrefixStep::MapDrives => {
+                let prefix = self.real_prefix();
+
+                for (drive, path) in &self.drives {
+                    WineDrives::map_folder(&prefix, *drive, path)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Get name of the wine build which last initialized or updated the prefix
+/// 
+/// Returns `None` if it wasn't recorded
+pub fn get_prefix_runner(prefix: impl AsRef<Path>) -> Option<String> {
+    std::fs::read_to_string(prefix.as_ref().join(RUNNER_FILE)).ok()
+        .map(|runner| runner.trim().to_string())
+        .filter(|runner| !runner.is_empty())
+}
+
+/// Record name of the wine build which initialized or updated the prefix
+pub fn set_prefix_runner(prefix: impl AsRef<Path>, runner: impl AsRef<str>) -> anyhow::Result<()> {
+    let path = prefix.as_ref().join(RUNNER_FILE);
+    let temp = path.with_extension("tmp");
+
+    // The file is replaced so it's never read half-written
+    std::fs::write(&temp, runner.as_ref())?;
+    std::fs::rename(temp, path)?;
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use super::prefix::{PrefixManager, PrefixProgress};
+
+/// Name of the file which marks finished template
+pub const TEMPLATE_MARKER: &str = ".launcher-template";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CloneMethod {
+    /// Files share data blocks until they're changed
+    Reflink,
+
+    Copy
+}
+
+/// Storage of the pristine wine prefixes, one per wine build
+/// 
+/// New prefixes are cloned from them instead of running `wineboot`
+/// and installing DXVK and fonts again
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrefixTemplates {
+    pub folder: PathBuf
+}
+
+impl PrefixTemplates {
+    #[inline]
+    pub fn new(folder: impl Into<PathBuf>) -> Self {
+        Self {
+            folder: folder.into()
+        }
+    }
+
+    #[inline]
+    /// Get path to the wine build's template
+    pub fn template(&self, wine: impl AsRef<str>) -> PathBuf {
+        self.folder.join(wine.as_ref())
+    }
+
+    #[inline]
+    /// Check if the wine build's template was built
+    pub fn exists(&self, wine: impl AsRef<str>) -> bool {
+        self.template(wine).join(TEMPLATE_MARKER).exists()
+    }
+
+    /// Build template for the wine build
+    /// 
+    /// Template is created by the given manager without drives mapping,
+    /// so it doesn't depend on any game
+    #[tracing::instrument(level = "info", skip(manager, updater))]
+    pub fn build(&self, wine: &str, manager: &PrefixManager, updater: impl Fn(PrefixProgress)) -> anyhow::Result<PathBuf> {
+        tracing::info!("Building wine prefix template");
+
+        let path = self.template(wine);
+
+        // Remove unfinished template
+        if path.exists() {
+            std::fs::remove_dir_all(&path)?;
+        }
+
+        let mut template = PrefixManager::new(manager.wine.clone(), &path)
+            .with_fonts(manager.fonts.iter().copied());
+
+        template.dxvk = manager.dxvk.clone();
+        template.dxvk_version = manager.dxvk_version.clone();
+        template.dxvk_params = manager.dxvk_params;
+        template.overrides = manager.overrides.clone();
+        template.runner = manager.runner.clone();
+
+        template.create(updater)?;
+
+        std::fs::write(path.join(TEMPLATE_MARKER), "")?;
+
+        Ok(path)
+    }
+
+    /// Remove the wine build's template
+    pub fn remove(&self, wine: impl AsRef<str>) -> anyhow::Result<()> {
+        let path = self.template(wine);
+
+        if path.exists() {
+            std::fs::remove_dir_all(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Clone template prefix to the given folder
+/// 
+/// Reflinks are used if the filesystem supports them, and plain copy otherwise.
+/// Cloned prefix never shares files with the template, so it can be changed freely
+#[tracing::instrument(level = "debug", ret)]
+pub fn clone_prefix(template: &Path, prefix: &Path) -> anyhow::Result<CloneMethod> {
+    if prefix.exists() {
+        anyhow::bail!("Wine prefix already exists: {prefix:?}");
+    }
+
+    if let Some(parent) = prefix.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let reflinked = Command::new("cp")
+        .arg("--archive")
+        .arg("--reflink=always")
+        .arg(template)
+        .arg(prefix)
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or_default();
+
+    if reflinked {
+        remove_marker(prefix)?;
+
+        return Ok(CloneMethod::Reflink);
+    }
+
+    if prefix.exists() {
+        std::fs::remove_dir_all(prefix)?;
+    }
+
+    copy_folder(template, prefix)?;
+
+    remove_marker(prefix)?;
+
+    Ok(CloneMethod::Copy)
+}
+
+fn remove_marker(prefix: &Path) -> std::io::Result<()> {
+    let marker = prefix.join(TEMPLATE_MARKER);
+
+    if marker.exists() {
+        std::fs::remove_file(marker)?;
+    }
+
+    Ok(())
+}
+
+fn copy_folder(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(to)?;
+
+    for entry in from.read_dir()?.flatten() {
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+
+        let file_type = entry.file_type()?;
+
+        if file_type.is_symlink() {
+            std::os::unix::fs::symlink(std::fs::read_link(&source)?, &target)?;
+        }
+
+        else if file_type.is_dir() {
+            copy_folder(&source, &target)?;
+        }
+
+        else {
+            std::fs::copy(&source, &target)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,218 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use super::{Group, Version, Files, Features, Bundle};
+
+/// Name of the wine group with custom builds
+pub const CUSTOM_GROUP_NAME: &str = "custom";
+
+/// Name of the wine version installed in the system
+pub const SYSTEM_WINE_NAME: &str = "system-wine";
+
+/// Command used to launch the game with custom proton builds
+const PROTON_COMMAND: &str = "python3 '%build%/proton' waitforexitandrun";
+
+/// Environment variables needed by the proton script
+/// 
+/// Prefix of the proton builds is stored in the `pfx` folder of the compat data path
+const PROTON_ENV: &[(&str, &str)] = &[
+    ("STEAM_COMPAT_DATA_PATH", "%prefix%"),
+    ("STEAM_COMPAT_CLIENT_INSTALL_PATH", "")
+];
+
+/// Folders which exist in 64 bit builds without `wine64` binary
+const WOW64_FOLDERS: &[&str] = &[
+    "lib/wine/x86_64-windows",
+    "lib64/wine/x86_64-windows",
+    "lib/x86_64-linux-gnu/wine/x86_64-windows"
+];
+
+/// Wine build from the local folder
+/// 
+/// Builds are not downloaded by the launcher,
+/// but their prefixes are managed by it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomWine {
+    pub name: String,
+    pub title: String,
+
+    /// Folder with the wine build
+    pub path: PathBuf,
+
+    /// Wine binaries relative to the `path`
+    /// 
+    /// Detected automatically if not set
+    pub files: Option<Files>,
+
+    pub features: Option<Features>
+}
+
+impl From<&JsonValue> for CustomWine {
+    fn from(value: &JsonValue) -> Self {
+        let name = value.get("name")
+            .and_then(|value| value.as_str())
+            .map(String::from)
+            .unwrap_or_default();
+
+        Self {
+            title: value.get("title")
+                .and_then(|value| value.as_str())
+                .map(String::from)
+                .unwrap_or_else(|| name.clone()),
+
+            path: value.get("path")
+                .and_then(|value| value.as_str())
+                .map(PathBuf::from)
+                .unwrap_or_default(),
+
+            files: value.get("files")
+                .and_then(|value| serde_json::from_value(value.to_owned()).ok()),
+
+            features: value.get("features").map(Features::from),
+
+            name
+        }
+    }
+}
+
+impl CustomWine {
+    /// Convert the build to the wine version
+    /// 
+    /// Returns `None` if the build's folder doesn't exist or its binaries weren't found
+    pub fn to_version(&self) -> Option<Version> {
+        if self.name.is_empty() || !self.path.is_dir() {
+            return None;
+        }
+
+        let files = match &self.files {
+            Some(files) => files.clone(),
+            None => detect_files(&self.path)?
+        };
+
+        Some(get_version(&self.name, &self.title, &self.path, files, self.features.clone()))
+    }
+}
+
+/// Detect wine binaries of the build in the given folder
+/// 
+/// Supported layouts are proton builds with the `proton` script,
+/// and wine builds with `bin/wine` and optionally `bin/wine64` binaries
+pub fn detect_files(folder: impl AsRef<Path>) -> Option<Files> {
+    let folder = folder.as_ref();
+
+    if folder.join("proton").is_file() {
+        return Some(Files {
+            wine: String::from("proton"),
+            wine64: None,
+            wineserver: None,
+            wineboot: None
+        });
+    }
+
+    let binary = |name: &str| {
+        folder.join("bin").join(name).is_file()
+            .then(|| format!("bin/{name}"))
+    };
+
+    let wine = binary("wine");
+
+    // New WoW64 builds have only one binary which can run 64 bit apps
+    let wine64 = binary("wine64").or_else(|| {
+        WOW64_FOLDERS.iter()
+            .any(|wow64| folder.join(wow64).is_dir())
+            .then(|| wine.clone())
+            .flatten()
+    });
+
+    Some(Files {
+        wine: wine.or_else(|| wine64.clone())?,
+        wine64,
+        wineserver: binary("wineserver"),
+        wineboot: binary("wineboot")
+    })
+}
+
+/// Get wine version installed in the system
+/// 
+/// Returns `None` if wine is not available in `PATH`
+pub fn get_system_wine() -> Option<Version> {
+    let wine = crate::tools::find_binary("wine")?;
+
+    // Symlinks are not resolved so `/usr/bin/wine` gives `/usr`
+    let folder = wine.parent()?.parent()?;
+
+    let files = detect_files(folder)?;
+
+    Some(get_version(SYSTEM_WINE_NAME, "System wine", folder, files, None))
+}
+
+/// Get wine group with the system wine and the given custom builds
+/// 
+/// Builds which can't be used are skipped with a warning.
+/// Returns `None` if the group is empty
+pub fn get_custom_group(custom: &[CustomWine]) -> Option<Group> {
+    let mut versions = Vec::with_capacity(custom.len() + 1);
+
+    versions.extend(get_system_wine());
+
+    for wine in custom {
+        match wine.to_version() {
+            Some(version) => versions.push(version),
+            None => tracing::warn!("Skipping custom wine build {:?}: folder or binaries not found: {:?}", wine.name, wine.path)
+        }
+    }
+
+    if versions.is_empty() {
+        return None;
+    }
+
+    Some(Group {
+        name: String::from(CUSTOM_GROUP_NAME),
+        title: String::from("Custom"),
+        features: None,
+        managed: true,
+        versions
+    })
+}
+
+fn get_version(name: &str, title: &str, folder: &Path, files: Files, features: Option<Features>) -> Version {
+    let is_proton = files.wine == "proton";
+
+    // Proton builds have their own DXVK
+    let mut features = features.unwrap_or_else(|| Features {
+        need_dxvk: !is_proton,
+        ..Features::default()
+    });
+
+    // Proton script should be run the same way as in the managed proton builds
+    if is_proton {
+        if features.bundle.is_none() {
+            features.bundle = Some(Bundle::Proton);
+        }
+
+        if features.command.is_none() {
+            features.command = Some(String::from(PROTON_COMMAND));
+            features.compact_launch = true;
+        }
+
+        for (key, value) in PROTON_ENV {
+            features.env.entry(key.to_string())
+                .or_insert_with(|| value.to_string());
+        }
+    }
+
+    // Prefixes of the custom builds are managed by the launcher
+    features.managed_prefix = None;
+
+    Version {
+        name: name.to_string(),
+        title: title.to_string(),
+        uri: folder.to_string_lossy().to_string(),
+        format: None,
+        files,
+        managed: true,
+        features: Some(features)
+    }
+}
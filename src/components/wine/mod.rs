@@ -7,10 +7,13 @@ use serde_json::Value as JsonValue;
 use wincompatlib::prelude::*;
 
 use super::loader::ComponentsLoader;
+use super::download::{download_component, DownloadProgress};
 
 mod unified_wine;
+pub mod custom;
 
 pub use unified_wine::UnifiedWine;
+pub use custom::CustomWine;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Group {
@@ -22,19 +25,15 @@ pub struct Group {
 }
 
 impl Group {
+    #[inline]
     /// Find wine group with given name in components index
     /// 
     /// This method will also check all version names within this group, so both `wine-ge-proton` and `lutris-GE-Proton7-37-x86_64` will work
+    /// 
+    /// Custom builds are not searched. Use `ComponentsLoader::find_wine_group`
+    /// with the loader from `Config::get_components_loader` to find them too
     pub fn find_in<T: Into<PathBuf>, F: AsRef<str>>(components: T, name: F) -> anyhow::Result<Option<Self>> {
-        let name = name.as_ref();
-
-        for group in get_groups(components)? {
-            if group.name == name || group.versions.iter().any(move |version| version.name == name) {
-                return Ok(Some(group));
-            }
-        }
-
-        Ok(None)
+        ComponentsLoader::new(components).find_wine_group(name)
     }
 }
 
@@ -58,6 +57,10 @@ pub struct Features {
     /// - `%temp%` - path to temp folder specified in config file
     /// - `%launcher%` - path to launcher folder
     /// - `%game%` - path to the game
+    /// - `%patch%` - path to the patch folder (only for games which need it)
+    /// - `%edition%` - game edition (if the game has editions)
+    /// - `%game_version%` - installed game version
+    /// - `%wine_name%` - name of the selected wine build
     pub command: Option<String>,
 
     /// Standard environment variables that are applied when you launch the game
@@ -68,6 +71,10 @@ pub struct Features {
     /// - `%temp%` - path to temp folder specified in config file
     /// - `%launcher%` - path to launcher folder
     /// - `%game%` - path to the game
+    /// - `%patch%` - path to the patch folder (only for games which need it)
+    /// - `%edition%` - game edition (if the game has editions)
+    /// - `%game_version%` - installed game version
+    /// - `%wine_name%` - name of the selected wine build
     pub env: HashMap<String, String>,
 
     /// Managed prefix. Not set unless using the Steam variance.
@@ -181,35 +188,43 @@ impl Version {
         }
     }
 
+    /// Check if the wine prefix is managed by Steam instead of the launcher
+    /// 
+    /// Custom builds are managed too, but the launcher creates their prefixes
+    pub fn is_prefix_managed(&self) -> bool {
+        self.managed && self.features.as_ref()
+            .map(|features| features.managed_prefix.is_some())
+            .unwrap_or_default()
+    }
+
     /// Get latest recommended wine version
+    /// 
+    /// It's the first version of the first group in the components index.
+    /// Custom and managed builds are never recommended, so their groups are skipped
     pub fn latest<T: Into<PathBuf>>(components: T) -> anyhow::Result<Self> {
-        Ok(get_groups(components)?[0].versions[0].clone())
+        get_groups(components)?
+            .into_iter()
+            .filter(|group| !group.managed)
+            .find_map(|group| group.versions.into_iter().find(|version| !version.managed))
+            .ok_or_else(|| anyhow::anyhow!("Components index doesn't have recommended wine versions"))
     }
 
+    #[inline]
     /// Find wine version with given name in components index
+    /// 
+    /// Custom builds are not searched. Use `ComponentsLoader::find_wine_version`
+    /// with the loader from `Config::get_components_loader` to find them too
     pub fn find_in<T: Into<PathBuf>, F: AsRef<str>>(components: T, name: F) -> anyhow::Result<Option<Self>> {
-        let name = name.as_ref();
-
-        for group in get_groups(components)? {
-            if let Some(version) = group.versions.into_iter().find(move |version| version.name == name) {
-                return Ok(Some(version));
-            }
-        }
-
-        Ok(None)
+        ComponentsLoader::new(components).find_wine_version(name)
     }
 
+    #[inline]
     /// Find wine group current version belongs to
+    /// 
+    /// Custom builds are not searched. Use `ComponentsLoader::find_wine_group`
+    /// with the loader from `Config::get_components_loader` to find them too
     pub fn find_group<T: Into<PathBuf>>(&self, components: T) -> anyhow::Result<Option<Group>> {
-        let name = self.name.as_str();
-
-        for group in get_groups(components)? {
-            if group.versions.iter().any(move |version| version.name == name) {
-                return Ok(Some(group));
-            }
-        }
-
-        Ok(None)
+        ComponentsLoader::new(components).find_wine_group(&self.name)
     }
 
     #[inline]
@@ -218,6 +233,18 @@ impl Version {
         folder.into().join(&self.name).exists()
     }
 
+    /// Download wine build and extract it to the `folder/name` folder
+    /// 
+    /// `progress` returns `false` to cancel downloading.
+    /// Managed builds are not downloaded. See `download_component` for details
+    pub fn download_to<T: Into<PathBuf>>(&self, folder: T, progress: impl Fn(DownloadProgress) -> bool) -> anyhow::Result<PathBuf> {
+        if self.managed {
+            anyhow::bail!("Managed wine build can't be downloaded: {}", self.name);
+        }
+
+        download_component(&self.uri, self.format.as_deref(), &self.name, &folder.into(), progress)
+    }
+
     #[inline]
     /// Return this version's features
     pub fn version_features(&self) -> Option<Features> {
@@ -238,6 +265,8 @@ impl Version {
 
     /// Return this version's features if they persist, or
     /// try to return group's features otherwise
+    /// 
+    /// Custom builds always have their own features
     pub fn features<T: Into<PathBuf>>(&self, components: T) -> anyhow::Result<Option<Features>> {
         if self.features.is_some() {
             Ok(self.features.clone())
@@ -315,28 +344,19 @@ pub struct Files {
 }
 
 #[inline]
+/// Get wine groups from components index
+/// 
+/// Only the system wine is added as the custom build. Use `ComponentsLoader::get_wine_versions`
+/// with the loader from `Config::get_components_loader` to list the custom builds from the config
 pub fn get_groups<T: Into<PathBuf>>(components: T) -> anyhow::Result<Vec<Group>> {
     ComponentsLoader::new(components).get_wine_versions()
 }
 
+#[inline]
 /// List downloaded wine versions in some specific folder
+/// 
+/// Custom builds from the config are not listed. Use `ComponentsLoader::get_downloaded_wine_versions`
+/// with the loader from `Config::get_components_loader` to list them too
 pub fn get_downloaded<T: Into<PathBuf>>(components: T, folder: T) -> anyhow::Result<Vec<Group>> {
-    let mut downloaded = Vec::new();
-
-    let folder: PathBuf = folder.into();
-
-    for mut group in get_groups(components)? {
-        if group.managed {
-            // special case: Runners are externally managed.
-            downloaded.push(group);
-            continue;
-        }
-        group.versions.retain(|version| folder.join(&version.name).exists());
-
-        if !group.versions.is_empty() {
-            downloaded.push(group);
-        }
-    }
-
-    Ok(downloaded)
+    ComponentsLoader::new(components).get_downloaded_wine_versions(folder.into())
 }
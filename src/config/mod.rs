@@ -22,6 +22,14 @@ pub trait ConfigExt {
     /// Clone given schema
     fn clone_schema(schema: &Self::Schema) -> Self::Schema;
 
+    #[inline]
+    /// Get copy of the given schema with the active profile merged over the base values
+    /// 
+    /// Returns unchanged copy by default
+    fn resolve_schema(schema: &Self::Schema) -> Self::Schema {
+        Self::clone_schema(schema)
+    }
+
     /// Get config data
     /// 
     /// This method will load config from file once and store it into the memory.
@@ -29,6 +37,15 @@ pub trait ConfigExt {
     /// that always loads config directly from the file. This will also update in-memory config
     fn get() -> anyhow::Result<Self::Schema>;
 
+    #[inline]
+    /// Get config data with the active profile applied
+    /// 
+    /// Use this method to run the game. Resolved config should not be
+    /// saved back because profile values would overwrite the base ones
+    fn get_resolved() -> anyhow::Result<Self::Schema> {
+        Ok(Self::resolve_schema(&Self::get()?))
+    }
+
     /// Update in-memory config data
     /// 
     /// Use `update_raw` if you want to update config file itself
@@ -1,8 +1,8 @@
-use std::process::Command;
-
 use serde::{Serialize, Deserialize};
 use serde_json::Value as JsonValue;
 
+use crate::tools::{self, Tool};
+
 pub mod size;
 pub mod framerate;
 pub mod window_type;
@@ -87,12 +87,22 @@ impl From<&JsonValue> for Gamescope {
 // TODO: temporary workaround for transition period, will be removed in future
 #[cached::proc_macro::cached]
 fn is_legacy_version() -> bool {
+    // Newer gamescope versions print their version in --help
+    if let Some(version) = Tool::Gamescope.version() {
+        let mut parts = version.split('.').map(|part| part.parse::<u64>().unwrap_or_default());
+
+        let major = parts.next().unwrap_or_default();
+        let minor = parts.next().unwrap_or_default();
+
+        return (major, minor) < (3, 12);
+    }
+
     // gamescope doesn't have --version, so parsing --help instead
-    Command::new("gamescope").arg("--help").output()
+    // also for whatever reason --help is printed to stderr
+    tools::output("gamescope", &["--help"])
 
         // if no --filter, then it's legacy version
-        // also for whatever reason --help is printed to stderr
-        .map(|help| !String::from_utf8_lossy(&help.stderr).contains("-F, --filter"))
+        .map(|help| !help.contains("-F, --filter"))
 
         // If failed to launch gamescope, then yes, it's legacy (it's not but meh)
         .unwrap_or(true)
@@ -104,72 +114,73 @@ impl Gamescope {
         is_legacy_version()
     }
 
-    pub fn get_command(&self) -> Option<String> {
+    /// Get `gamescope [args]` command arguments
+    pub fn get_args(&self) -> Option<Vec<String>> {
         // https://github.com/bottlesdevs/Bottles/blob/b908311348ed1184ead23dd76f9d8af41ff24082/src/backend/wine/winecommand.py#L478
         // https://github.com/ValveSoftware/gamescope#options
         if self.enabled {
-            let mut gamescope = String::from("gamescope");
+            let mut gamescope = vec![String::from("gamescope")];
 
             // Set window type
             match self.window_type {
-                WindowType::Borderless => gamescope += " -b",
-                WindowType::Fullscreen => gamescope += " -f"
+                WindowType::Borderless => gamescope.push(String::from("-b")),
+                WindowType::Fullscreen => gamescope.push(String::from("-f"))
             }
 
             // Set game width
             if self.game.width > 0 {
-                gamescope += &format!(" -w {}", self.game.width);
+                gamescope.extend([String::from("-w"), self.game.width.to_string()]);
             }
 
             // Set game height
             if self.game.height > 0 {
-                gamescope += &format!(" -h {}", self.game.height);
+                gamescope.extend([String::from("-h"), self.game.height.to_string()]);
             }
 
             // Set gamescope width
             if self.gamescope.width > 0 {
-                gamescope += &format!(" -W {}", self.gamescope.width);
+                gamescope.extend([String::from("-W"), self.gamescope.width.to_string()]);
             }
 
             // Set gamescope height
             if self.gamescope.height > 0 {
-                gamescope += &format!(" -H {}", self.gamescope.height);
+                gamescope.extend([String::from("-H"), self.gamescope.height.to_string()]);
             }
 
             // Set focused framerate limit
             if self.framerate.focused > 0 {
-                gamescope += &format!(" -r {}", self.framerate.focused);
+                gamescope.extend([String::from("-r"), self.framerate.focused.to_string()]);
             }
 
             // Set unfocused framerate limit
             if self.framerate.unfocused > 0 {
-                gamescope += &format!(" -o {}", self.framerate.unfocused);
+                gamescope.extend([String::from("-o"), self.framerate.unfocused.to_string()]);
             }
 
             // Set integer scaling
             if self.integer_scaling {
-                gamescope += if Self::is_legacy_version() {
-                    " -n"
+                if Self::is_legacy_version() {
+                    gamescope.push(String::from("-n"));
                 } else {
-                    " -S integer"
+                    gamescope.extend([String::from("-S"), String::from("integer")]);
                 }
             }
 
             // Set FSR support
             if self.fsr {
-                gamescope += if Self::is_legacy_version() {
-                    " -U"
+                if Self::is_legacy_version() {
+                    gamescope.push(String::from("-U"));
                 } else {
-                    " -F fsr"
+                    gamescope.extend([String::from("-F"), String::from("fsr")]);
                 }
             }
 
             // Set NIS (Nvidia Image Scaling) support
             if self.nis {
-                gamescope += if Self::is_legacy_version() {
-                    " -Y"
+                if Self::is_legacy_version() {
+                    gamescope.push(String::from("-Y"));
                 } else {
-                    " -F nis"
+                    gamescope.extend([String::from("-F"), String::from("nis")]);
                 }
             }
 
@@ -180,4 +191,10 @@ impl Gamescope {
             None
         }
     }
+
+    #[inline]
+    /// Get `gamescope [args]` command
+    pub fn get_command(&self) -> Option<String> {
+        self.get_args().map(crate::shell::join)
+    }
 }
@@ -0,0 +1,110 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use enum_ordinalize::Ordinalize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Ordinalize, Serialize, Deserialize)]
+pub enum HookFailure {
+    /// Stop game launching
+    Fail,
+
+    /// Print warning and continue
+    Warn
+}
+
+impl Default for HookFailure {
+    #[inline]
+    fn default() -> Self {
+        Self::Warn
+    }
+}
+
+impl From<&JsonValue> for HookFailure {
+    #[inline]
+    fn from(value: &JsonValue) -> Self {
+        serde_json::from_value(value.clone()).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hook {
+    /// Command executed by bash
+    pub command: String,
+
+    /// Time in seconds after which the command is killed. `0` disables timeout
+    pub timeout: u64,
+
+    /// What to do if the command failed or timed out
+    pub on_failure: HookFailure
+}
+
+impl Default for Hook {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            command: String::new(),
+            timeout: 30,
+            on_failure: HookFailure::default()
+        }
+    }
+}
+
+impl From<&JsonValue> for Hook {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        // Allow hooks to be set as plain strings
+        if let Some(command) = value.as_str() {
+            return Self {
+                command: command.to_string(),
+                ..default
+            };
+        }
+
+        Self {
+            command: value.get("command")
+                .and_then(JsonValue::as_str)
+                .map(String::from)
+                .unwrap_or(default.command),
+
+            timeout: value.get("timeout")
+                .and_then(JsonValue::as_u64)
+                .unwrap_or(default.timeout),
+
+            on_failure: value.get("on_failure")
+                .map(HookFailure::from)
+                .unwrap_or(default.on_failure)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Hooks {
+    /// Commands executed before running the game
+    pub pre_launch: Vec<Hook>,
+
+    /// Commands executed after the game is closed
+    /// 
+    /// Additionally support `%exit_code%` and `%playtime%` (in seconds) keywords
+    pub post_exit: Vec<Hook>
+}
+
+impl From<&JsonValue> for Hooks {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        let get_hooks = |name: &str| {
+            value.get(name)
+                .and_then(JsonValue::as_array)
+                .map(|hooks| hooks.iter()
+                    .map(Hook::from)
+                    .filter(|hook| !hook.command.is_empty())
+                    .collect::<Vec<_>>())
+        };
+
+        Self {
+            pre_launch: get_hooks("pre_launch").unwrap_or(default.pre_launch),
+            post_exit: get_hooks("post_exit").unwrap_or(default.post_exit)
+        }
+    }
+}
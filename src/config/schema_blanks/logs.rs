@@ -0,0 +1,108 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Logs {
+    /// Write game output to the log file
+    pub enabled: bool,
+
+    /// Amount of log files to keep. `0` keeps all of them
+    pub retention: u64,
+
+    /// Environment variables which values should be hidden in logs
+    /// 
+    /// Keys are case insensitive and can contain `*` wildcards
+    pub redacted_env: Vec<String>
+}
+
+impl Default for Logs {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            retention: 10,
+
+            redacted_env: vec![
+                String::from("*TOKEN*"),
+                String::from("*SECRET*"),
+                String::from("*PASSWORD*"),
+                String::from("*PASSWD*"),
+                String::from("*API_KEY*"),
+                String::from("*AUTH*")
+            ]
+        }
+    }
+}
+
+impl From<&JsonValue> for Logs {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        Self {
+            enabled: value.get("enabled")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.enabled),
+
+            retention: value.get("retention")
+                .and_then(JsonValue::as_u64)
+                .unwrap_or(default.retention),
+
+            redacted_env: match value.get("redacted_env").and_then(JsonValue::as_array) {
+                Some(values) => values.iter()
+                    .filter_map(JsonValue::as_str)
+                    .map(String::from)
+                    .collect(),
+
+                None => default.redacted_env
+            }
+        }
+    }
+}
+
+impl Logs {
+    /// Check if the environment variable's value should be hidden in logs
+    /// 
+    /// ```
+    /// use anime_launcher_sdk::config::schema_blanks::prelude::Logs;
+    /// 
+    /// let logs = Logs::default();
+    /// 
+    /// assert!(logs.is_redacted("GITHUB_TOKEN"));
+    /// assert!(logs.is_redacted("my_secret_value"));
+    /// assert!(!logs.is_redacted("WINEPREFIX"));
+    /// ```
+    pub fn is_redacted(&self, key: impl AsRef<str>) -> bool {
+        let key = key.as_ref().to_uppercase();
+
+        self.redacted_env.iter()
+            .any(|pattern| wildcard_match(&pattern.to_uppercase(), &key))
+    }
+}
+
+/// Match text with the pattern where `*` means any amount of any chars
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+
+    // split always returns at least one item
+    let first = parts.next().unwrap_or_default();
+
+    let Some(mut text) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts = parts.collect::<Vec<_>>();
+
+    // Pattern without wildcards
+    let Some((last, middle)) = parts.split_last() else {
+        return text.is_empty();
+    };
+
+    for part in middle {
+        match text.find(part) {
+            Some(pos) => text = &text[pos + part.len()..],
+            None => return false
+        }
+    }
+
+    text.len() >= last.len() && text.ends_with(last)
+}
@@ -5,6 +5,9 @@ pub mod hud;
 pub mod fps;
 pub mod window_mode;
 pub mod dxvk;
+pub mod logs;
+pub mod hooks;
+pub mod profile;
 
 pub mod wine;
 pub mod gamescope;
@@ -19,6 +22,9 @@ pub mod prelude {
     pub use super::hud::HUD;
     pub use super::fps::Fps;
     pub use super::window_mode::WindowMode;
+    pub use super::logs::Logs;
+    pub use super::hooks::*;
+    pub use super::profile::Profile;
 
     pub use super::wine::prelude::*;
     pub use super::gamescope::prelude::*;
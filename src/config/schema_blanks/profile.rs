@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+use super::prelude::*;
+
+/// Named set of settings which are merged over the base config
+/// 
+/// Only set values are overridden. Gamescope, HUD and FSR settings are stored
+/// as written in the config, so only their set fields override the base ones
+/// (see `Profile::merge`). Environment variables are added to the base ones,
+/// replacing variables with the same names
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Profile {
+    pub gamescope: Option<JsonValue>,
+    pub hud: Option<JsonValue>,
+    pub fsr: Option<JsonValue>,
+    pub gamemode: Option<bool>,
+    pub sync: Option<WineSync>,
+    pub environment: HashMap<String, String>,
+    pub command: Option<String>
+}
+
+impl From<&JsonValue> for Profile {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        // Null and missing values both mean "not overridden"
+        let get = |name: &str| value.get(name).filter(|value| !value.is_null());
+
+        Self {
+            gamescope: get("gamescope")
+                .cloned()
+                .or(default.gamescope),
+
+            hud: get("hud")
+                .cloned()
+                .or(default.hud),
+
+            fsr: get("fsr")
+                .cloned()
+                .or(default.fsr),
+
+            gamemode: get("gamemode")
+                .and_then(JsonValue::as_bool)
+                .or(default.gamemode),
+
+            sync: get("sync")
+                .map(WineSync::from)
+                .or(default.sync),
+
+            environment: match get("environment").and_then(JsonValue::as_object) {
+                Some(values) => values.iter()
+                    .filter_map(|(name, value)| value.as_str().map(|value| (name.clone(), value.to_string())))
+                    .collect(),
+
+                None => default.environment
+            },
+
+            command: get("command")
+                .and_then(JsonValue::as_str)
+                .map(String::from)
+                .or(default.command)
+        }
+    }
+}
+
+impl Profile {
+    /// Merge profile's values over the base ones
+    /// 
+    /// Objects are merged field by field, so fields missing in the profile keep
+    /// their base values. Other values replace the base ones
+    /// 
+    /// ```
+    /// use serde_json::json;
+    /// 
+    /// use anime_launcher_sdk::config::schema_blanks::prelude::*;
+    /// 
+    /// let base = Fsr {
+    ///     strength: 3,
+    ///     enabled: false,
+    ///     ..Fsr::default()
+    /// };
+    /// 
+    /// let fsr = Profile::merge(&base, &json!({ "enabled": true }));
+    /// 
+    /// assert_eq!(fsr.strength, 3);
+    /// assert!(fsr.enabled);
+    /// ```
+    pub fn merge<T>(base: &T, values: &JsonValue) -> T
+    where
+        T: Serialize + for<'a> From<&'a JsonValue>
+    {
+        let mut merged = serde_json::to_value(base)
+            .unwrap_or(JsonValue::Null);
+
+        merge_json(&mut merged, values);
+
+        T::from(&merged)
+    }
+}
+
+fn merge_json(base: &mut JsonValue, values: &JsonValue) {
+    match (base, values) {
+        (JsonValue::Object(base), JsonValue::Object(values)) => {
+            for (key, value) in values {
+                match base.get_mut(key) {
+                    Some(base) => merge_json(base, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        (base, values) => *base = values.clone()
+    }
+}
@@ -124,30 +124,54 @@ impl Sandbox {
     /// | <mounts/binds> | <mounts/binds> | bind | true |
     /// | <mounts/symlinks> | <mounts/symlinks> | symlink | true |
     pub fn get_command(&self, wine_dir: impl AsRef<str>, prefix_dir: impl AsRef<str>, game_dir: impl AsRef<str>) -> String {
-        let mut command = String::from("bwrap --ro-bind / /");
+        crate::shell::join(self.get_args(wine_dir.as_ref(), prefix_dir.as_ref(), game_dir.as_ref()))
+    }
+
+    /// Return `bwrap [args]` command arguments
+    /// 
+    /// See `get_command` for the list of mounts
+    pub fn get_args(&self, wine_dir: impl AsRef<Path>, prefix_dir: impl AsRef<Path>, game_dir: impl AsRef<Path>) -> Vec<String> {
+        let prefix_mount = vec![
+            String::from("--bind"),
+            prefix_dir.as_ref().to_string_lossy().to_string(),
+            String::from("/tmp/sandbox/prefix")
+        ];
+
+        self.get_args_with_prefix(wine_dir, prefix_mount, game_dir)
+    }
+
+    /// Return `bwrap [args]` command arguments with custom
+    /// arguments mounting the wine prefix to `/tmp/sandbox/prefix`
+    /// 
+    /// Used to mount the prefix overlay
+    pub fn get_args_with_prefix(&self, wine_dir: impl AsRef<Path>, prefix_mount: Vec<String>, game_dir: impl AsRef<Path>) -> Vec<String> {
+        let mut command = vec![
+            String::from("bwrap"),
+            String::from("--ro-bind"), String::from("/"), String::from("/")
+        ];
 
-        command.push_str(" --bind /tmp /tmp");
-        command.push_str(" --bind /proc /proc");
-        command.push_str(" --dev-bind /dev /dev");
+        command.extend(["--bind", "/tmp", "/tmp"].map(String::from));
+        command.extend(["--bind", "/proc", "/proc"].map(String::from));
+        command.extend(["--dev-bind", "/dev", "/dev"].map(String::from));
 
         if let Some(hostname) = &self.hostname {
-            command += &format!(" --hostname '{hostname}'");
+            command.extend([String::from("--hostname"), hostname.clone()]);
         }
 
         if self.isolate_home {
             if Path::new("/home").is_dir() {
-                command.push_str(" --tmpfs /home");
+                command.extend(["--tmpfs", "/home"].map(String::from));
             }
 
             if Path::new("/var/home").is_dir() {
-                command.push_str(" --tmpfs /var/home");
+                command.extend(["--tmpfs", "/var/home"].map(String::from));
             }
 
             if let Ok(user) = std::env::var("USER") {
                 let dir = format!("/var/home/{}", user.trim());
 
                 if Path::new(&dir).is_dir() {
-                    command += &format!(" --tmpfs '{dir}'");
+                    command.extend([String::from("--tmpfs"), dir]);
                 }
             }
 
@@ -155,42 +179,50 @@ impl Sandbox {
                 let dir = home.trim();
 
                 if Path::new(&dir).is_dir() {
-                    command += &format!(" --tmpfs '{dir}'");
+                    command.extend([String::from("--tmpfs"), dir.to_string()]);
                 }
             }
         }
 
         for path in &self.private {
-            command += &format!(" --tmpfs '{}'", path.trim());
+            command.extend([String::from("--tmpfs"), path.trim().to_string()]);
         }
 
         for (from, to) in &self.mounts.read_only {
-            command += &format!(" --ro-bind '{}' '{}'", from.trim(), to.trim());
+            command.extend([String::from("--ro-bind"), from.trim().to_string(), to.trim().to_string()]);
         }
 
         for (from, to) in &self.mounts.bind {
-            command += &format!(" --bind '{}' '{}'", from.trim(), to.trim());
+            command.extend([String::from("--bind"), from.trim().to_string(), to.trim().to_string()]);
         }
 
         for (from, to) in &self.mounts.symlinks {
-            command += &format!(" --symlink '{}' '{}'", from.trim(), to.trim());
+            command.extend([String::from("--symlink"), from.trim().to_string(), to.trim().to_string()]);
         }
 
-        command += &format!(" --bind '{}' /tmp/sandbox/wine", wine_dir.as_ref());
-        command += &format!(" --bind '{}' /tmp/sandbox/prefix", prefix_dir.as_ref());
-        command += &format!(" --bind '{}' /tmp/sandbox/game", game_dir.as_ref());
+        command.extend([String::from("--bind"), wine_dir.as_ref().to_string_lossy().to_string(), String::from("/tmp/sandbox/wine")]);
+        command.extend(prefix_mount);
+        command.extend([String::from("--bind"), game_dir.as_ref().to_string_lossy().to_string(), String::from("/tmp/sandbox/game")]);
 
-        command.push_str(" --die-with-parent");
+        command.push(String::from("--die-with-parent"));
 
         // --unshare-pid breaks wine
 
-        command.push_str(" --unshare-user");
-        command.push_str(" --unshare-ipc");
-        command.push_str(" --unshare-uts");
-        command.push_str(" --unshare-cgroup");
+        command.push(String::from("--unshare-user"));
+        command.push(String::from("--unshare-ipc"));
+        command.push(String::from("--unshare-uts"));
+        command.push(String::from("--unshare-cgroup"));
 
         if let Some(args) = &self.args {
-            command.push_str(args.trim());
+            match crate::shell::split(args) {
+                Ok(args) => command.extend(args),
+
+                Err(err) => {
+                    tracing::warn!("Failed to parse additional sandbox arguments: {err}");
+
+                    command.extend(args.split_whitespace().map(String::from));
+                }
+            }
         }
 
         command
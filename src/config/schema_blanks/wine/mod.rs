@@ -3,6 +3,8 @@ pub mod wine_sync;
 pub mod wine_drives;
 pub mod virtual_desktop;
 pub mod shared_libraries;
+pub mod wineserver;
+pub mod overlay;
 
 pub mod prelude {
     pub use super::wine_drives::*;
@@ -11,6 +13,8 @@ pub mod prelude {
     pub use super::wine_sync::WineSync;
     pub use super::virtual_desktop::VirtualDesktop;
     pub use super::shared_libraries::SharedLibraries;
+    pub use super::wineserver::Wineserver;
+    pub use super::overlay::WineOverlay;
 }
 
 #[macro_export]
@@ -26,7 +30,9 @@ macro_rules! config_impl_wine_schema {
             pub borderless: bool,
             pub drives: WineDrives,
             pub virtual_desktop: VirtualDesktop,
-            pub shared_libraries: SharedLibraries
+            pub shared_libraries: SharedLibraries,
+            pub wineserver: Wineserver,
+            pub overlay: WineOverlay
         }
 
         impl Default for Wine {
@@ -43,7 +49,9 @@ macro_rules! config_impl_wine_schema {
                     borderless: false,
                     drives: WineDrives::default(),
                     virtual_desktop: VirtualDesktop::default(),
-                    shared_libraries: SharedLibraries::default()
+                    shared_libraries: SharedLibraries::default(),
+                    wineserver: Wineserver::default(),
+                    overlay: WineOverlay::default()
                 }
             }
         }
@@ -100,6 +108,14 @@ macro_rules! config_impl_wine_schema {
                     shared_libraries: value.get("shared_libraries")
                         .map(SharedLibraries::from)
                         .unwrap_or(default.shared_libraries),
+
+                    wineserver: value.get("wineserver")
+                        .map(Wineserver::from)
+                        .unwrap_or(default.wineserver),
+
+                    overlay: value.get("overlay")
+                        .map(WineOverlay::from)
+                        .unwrap_or(default.overlay)
                 }
             }
         }
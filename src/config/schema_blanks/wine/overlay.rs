@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+/// Shared base wine prefix with per-game changes stored on top of it
+/// 
+/// Game's `prefix` folder becomes a mount point of the merged view.
+/// Changes are stored in the `<prefix>.overlay` folder
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WineOverlay {
+    pub enabled: bool,
+
+    /// Path to the base prefix shared between the games
+    pub base: PathBuf
+}
+
+impl Default for WineOverlay {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            enabled: false,
+
+            base: dirs::data_dir()
+                .unwrap_or_default()
+                .join("anime-launcher-sdk")
+                .join("base-prefix")
+        }
+    }
+}
+
+impl From<&JsonValue> for WineOverlay {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        Self {
+            enabled: value.get("enabled")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.enabled),
+
+            base: value.get("base")
+                .and_then(JsonValue::as_str)
+                .map(PathBuf::from)
+                .unwrap_or(default.base)
+        }
+    }
+}
@@ -50,8 +50,17 @@ impl VirtualDesktop {
     #[inline]
     /// `explorer /desktop=[desktop_name],[width]x[height]`
     pub fn get_command<T: AsRef<str>>(&self, desktop_name: T) -> Option<String> {
+        self.get_args(desktop_name).map(|args| args.join(" "))
+    }
+
+    #[inline]
+    /// `["explorer", "/desktop=[desktop_name],[width]x[height]"]`
+    pub fn get_args<T: AsRef<str>>(&self, desktop_name: T) -> Option<Vec<String>> {
         if self.enabled {
-            Some(format!("explorer /desktop={},{}x{}", desktop_name.as_ref(), self.width, self.height))
+            Some(vec![
+                String::from("explorer"),
+                format!("/desktop={},{}x{}", desktop_name.as_ref(), self.width, self.height)
+            ])
         }
 
         else {
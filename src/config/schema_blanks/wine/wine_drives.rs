@@ -69,22 +69,26 @@ pub struct WineDrives {
 }
 
 impl WineDrives {
-    /// Automatically map all the configured folders
-    pub fn map_folders(&self, game_folder: impl Into<PathBuf>, prefix_folder: impl Into<PathBuf>) -> anyhow::Result<()> {
+    /// Get all the configured folders which should be mapped to the prefix drives
+    pub fn get_drives(&self, game_folder: impl Into<PathBuf>, prefix_folder: impl Into<PathBuf>) -> HashMap<AllowedDrives, PathBuf> {
         let mut drives = self.map_folders.clone();
 
-        let game_folder = game_folder.into();
-        let prefix_folder = prefix_folder.into();
-
         if self.drive_c {
-            drives.insert(AllowedDrives::C, prefix_folder.join("drive_c"));
+            drives.insert(AllowedDrives::C, prefix_folder.into().join("drive_c"));
         }
 
         if let Some(drive) = self.game_folder {
-            drives.insert(drive, game_folder);
+            drives.insert(drive, game_folder.into());
         }
 
-        for (drive, path) in drives {
+        drives
+    }
+
+    /// Automatically map all the configured folders
+    pub fn map_folders(&self, game_folder: impl Into<PathBuf>, prefix_folder: impl Into<PathBuf>) -> anyhow::Result<()> {
+        let prefix_folder = prefix_folder.into();
+
+        for (drive, path) in self.get_drives(game_folder, &prefix_folder) {
             Self::map_folder(&prefix_folder, drive, path)?;
         }
 
@@ -0,0 +1,49 @@
+use serde::{Serialize, Deserialize};
+use serde_json::Value as JsonValue;
+
+/// Wineserver management after the game exits
+/// 
+/// Wine processes like explorer with a virtual desktop or
+/// services can keep running after the game is closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Wineserver {
+    /// Wait for the wineserver and stop it after the game exits
+    pub enabled: bool,
+
+    /// Seconds to wait for the wineserver to exit by itself
+    pub timeout: u64,
+
+    /// Kill wine processes if they didn't stop gracefully
+    pub force: bool
+}
+
+impl Default for Wineserver {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout: 10,
+            force: true
+        }
+    }
+}
+
+impl From<&JsonValue> for Wineserver {
+    fn from(value: &JsonValue) -> Self {
+        let default = Self::default();
+
+        Self {
+            enabled: value.get("enabled")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.enabled),
+
+            timeout: value.get("timeout")
+                .and_then(JsonValue::as_u64)
+                .unwrap_or(default.timeout),
+
+            force: value.get("force")
+                .and_then(JsonValue::as_bool)
+                .unwrap_or(default.force)
+        }
+    }
+}
@@ -0,0 +1,371 @@
+use std::path::{Path, PathBuf};
+use std::os::unix::fs::PermissionsExt;
+
+use crate::components::loader::ComponentsLoader;
+use crate::components::wine::CustomWine;
+use crate::components::dxvk::Version as DxvkVersion;
+
+use crate::tools::Tool;
+
+/// Standard Vulkan ICD manifests folders
+pub const VULKAN_ICD_FOLDERS: &[&str] = &[
+    "/usr/share/vulkan/icd.d",
+    "/usr/local/share/vulkan/icd.d",
+    "/etc/vulkan/icd.d"
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// Something is not optimal, but the game will run
+    Warning,
+
+    /// The game will most likely fail to run
+    Error
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Diagnostic {
+    WineNotSelected,
+
+    /// Selected wine version is not listed in the components index or custom builds
+    WineNotFound(String),
+
+    /// Failed to read the components index
+    ComponentsIndexError(String),
+
+    WineBinaryMissing(PathBuf),
+    WineBinaryNotExecutable(PathBuf),
+
+    /// Prefix doesn't have `drive_c` folder
+    PrefixNotExists(PathBuf),
+
+    /// Prefix doesn't have `dosdevices` folder
+    DosdevicesMissing(PathBuf),
+
+    /// Drive symlink points to a folder which doesn't exist
+    BrokenDrive {
+        drive: String,
+        target: PathBuf
+    },
+
+    /// DXVK is not installed while selected wine version needs it
+    DxvkNotInstalled,
+
+    /// DXVK installed in the prefix is not listed in the components index
+    DxvkUnknown(String),
+
+    /// Failed to get DXVK version installed in the prefix
+    DxvkError(String),
+
+    /// Binary needed by an enabled setting is not available
+    ToolMissing {
+        binary: &'static str,
+        setting: &'static str
+    },
+
+    /// No Vulkan ICD manifests found. DXVK and most of wine builds need Vulkan
+    VulkanIcdMissing
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Self::BrokenDrive { .. } |
+            Self::DxvkNotInstalled |
+            Self::DxvkUnknown(_) |
+            Self::DxvkError(_) => Severity::Warning,
+
+            _ => Severity::Error
+        }
+    }
+
+    /// Get description of how the problem can be fixed
+    pub fn hint(&self) -> String {
+        match self {
+            Self::WineNotSelected => String::from("Select wine version in the launcher settings"),
+            Self::WineNotFound(_) => String::from("Select another wine version or update the components index"),
+            Self::ComponentsIndexError(_) => String::from("Delete components folder so the launcher could download it again"),
+
+            Self::WineBinaryMissing(_) => String::from("Wine build is damaged. Delete it and download it again"),
+            Self::WineBinaryNotExecutable(path) => format!("Run `chmod +x {:?}`", path),
+
+            Self::PrefixNotExists(_) => String::from("Create wine prefix in the launcher settings"),
+            Self::DosdevicesMissing(_) => String::from("Wine prefix is damaged. Delete it and create it again"),
+
+            Self::BrokenDrive { drive, .. } => format!("Remap or remove drive {drive} in the launcher settings"),
+
+            Self::DxvkNotInstalled => String::from("Install DXVK in the launcher settings"),
+            Self::DxvkUnknown(_) => String::from("Install DXVK version listed in the launcher settings"),
+            Self::DxvkError(_) => String::from("Reinstall DXVK in the launcher settings"),
+
+            Self::ToolMissing { binary, setting } => format!("Install {binary} using your package manager or disable {setting}"),
+
+            Self::VulkanIcdMissing => String::from("Install Vulkan drivers for your GPU (e.g. mesa-vulkan-drivers or nvidia-utils)")
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WineNotSelected => write!(f, "Wine version is not selected"),
+            Self::WineNotFound(name) => write!(f, "Wine version {name} is not found in the components index"),
+            Self::ComponentsIndexError(err) => write!(f, "Failed to read components index: {err}"),
+
+            Self::WineBinaryMissing(path) => write!(f, "Wine binary doesn't exist: {:?}", path),
+            Self::WineBinaryNotExecutable(path) => write!(f, "Wine binary is not executable: {:?}", path),
+
+            Self::PrefixNotExists(path) => write!(f, "Wine prefix doesn't exist: {:?}", path),
+            Self::DosdevicesMissing(path) => write!(f, "Wine prefix doesn't have dosdevices folder: {:?}", path),
+
+            Self::BrokenDrive { drive, target } => write!(f, "Drive {drive} points to missing folder {:?}", target),
+
+            Self::DxvkNotInstalled => write!(f, "DXVK is not installed"),
+            Self::DxvkUnknown(version) => write!(f, "Installed DXVK version {version} is unknown"),
+            Self::DxvkError(err) => write!(f, "Failed to get installed DXVK version: {err}"),
+
+            Self::ToolMissing { binary, setting } => write!(f, "{binary} is required by {setting} but is not installed"),
+
+            Self::VulkanIcdMissing => write!(f, "Vulkan drivers are not found")
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DiagnosticsReport {
+    pub diagnostics: Vec<Diagnostic>
+}
+
+impl DiagnosticsReport {
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    #[inline]
+    /// Check if the report has problems which will most likely break the game
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics.iter().any(|diagnostic| diagnostic.severity() == Severity::Error)
+    }
+
+    #[inline]
+    /// Get the highest severity of the found problems
+    pub fn severity(&self) -> Option<Severity> {
+        self.diagnostics.iter().map(Diagnostic::severity).max()
+    }
+
+    /// Get problems with given severity
+    pub fn with_severity(&self, severity: Severity) -> Vec<&Diagnostic> {
+        self.diagnostics.iter()
+            .filter(|diagnostic| diagnostic.severity() == severity)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiagnosticsParams {
+    /// Path to the components index
+    pub components: PathBuf,
+
+    /// Wine builds from the local folders
+    pub custom_wine: Vec<CustomWine>,
+
+    /// Name of the selected wine version
+    pub wine: Option<String>,
+
+    /// Folder with downloaded wine builds
+    pub wine_builds: PathBuf,
+
+    /// Real wine prefix path
+    pub prefix: PathBuf,
+
+    pub gamescope: bool,
+    pub gamemode: bool,
+    pub mangohud: bool,
+    pub sandbox: bool,
+
+    /// Wine prefix overlay is enabled
+    /// 
+    /// `fuse-overlayfs` is not needed if the sandbox is enabled too
+    pub overlay: bool
+}
+
+/// Check everything needed to run the game
+#[tracing::instrument(level = "debug", ret)]
+pub fn diagnose(params: &DiagnosticsParams) -> DiagnosticsReport {
+    let mut report = DiagnosticsReport::default();
+
+    check_wine(params, &mut report);
+    check_prefix(&params.prefix, &mut report);
+
+    let tools = [
+        (params.gamescope, Tool::Gamescope, "gamescope"),
+        (params.gamemode, Tool::GameMode, "gamemode"),
+        (params.mangohud, Tool::MangoHud, "MangoHUD"),
+        (params.sandbox, Tool::Bwrap, "sandbox"),
+
+        // Sandbox mounts the overlay itself
+        (params.overlay && !params.sandbox, Tool::FuseOverlayfs, "prefix overlay")
+    ];
+
+    for (enabled, tool, setting) in tools {
+        if enabled && !tool.is_available() {
+            report.diagnostics.push(Diagnostic::ToolMissing {
+                binary: tool.binary(),
+                setting
+            });
+        }
+    }
+
+    if !has_vulkan_icd() {
+        report.diagnostics.push(Diagnostic::VulkanIcdMissing);
+    }
+
+    report
+}
+
+fn check_wine(params: &DiagnosticsParams, report: &mut DiagnosticsReport) {
+    let Some(name) = &params.wine else {
+        report.diagnostics.push(Diagnostic::WineNotSelected);
+
+        return;
+    };
+
+    let wine = ComponentsLoader::new(&params.components)
+        .with_custom(params.custom_wine.iter().cloned())
+        .get_wine_versions()
+        .map(|groups| {
+            groups.into_iter()
+                .flat_map(|group| group.versions)
+                .find(|version| &version.name == name)
+        });
+
+    let wine = match wine {
+        Ok(Some(wine)) => wine,
+
+        Ok(None) => {
+            report.diagnostics.push(Diagnostic::WineNotFound(name.clone()));
+
+            return;
+        }
+
+        Err(err) => {
+            report.diagnostics.push(Diagnostic::ComponentsIndexError(err.to_string()));
+
+            return;
+        }
+    };
+
+    let folder = wine.get_runner_dir(&params.wine_builds);
+
+    let binaries = [
+        Some(&wine.files.wine),
+        wine.files.wine64.as_ref(),
+        wine.files.wineserver.as_ref(),
+        wine.files.wineboot.as_ref()
+    ];
+
+    for binary in binaries.into_iter().flatten() {
+        let path = folder.join(binary);
+
+        match path.metadata() {
+            Err(_) => report.diagnostics.push(Diagnostic::WineBinaryMissing(path)),
+
+            // Windows binaries (like wineboot.exe) are not executed directly
+            Ok(metadata) if metadata.permissions().mode() & 0o111 == 0 && !binary.ends_with(".exe") => {
+                report.diagnostics.push(Diagnostic::WineBinaryNotExecutable(path));
+            }
+
+            Ok(_) => ()
+        }
+    }
+
+    // DXVK is checked here because we need to know if selected wine needs it
+    let need_dxvk = wine.features(&params.components)
+        .ok()
+        .flatten()
+        .unwrap_or_default()
+        .need_dxvk;
+
+    if params.prefix.join("drive_c").exists() {
+        match wincompatlib::dxvk::Dxvk::get_version(&params.prefix) {
+            Ok(Some(version)) => match DxvkVersion::find_in(&params.components, &version) {
+                Ok(Some(_)) => (),
+                Ok(None) => report.diagnostics.push(Diagnostic::DxvkUnknown(version)),
+                Err(err) => report.diagnostics.push(Diagnostic::ComponentsIndexError(err.to_string()))
+            }
+
+            Ok(None) if need_dxvk => report.diagnostics.push(Diagnostic::DxvkNotInstalled),
+            Ok(None) => (),
+
+            Err(err) => report.diagnostics.push(Diagnostic::DxvkError(err.to_string()))
+        }
+    }
+}
+
+fn check_prefix(prefix: &Path, report: &mut DiagnosticsReport) {
+    if !prefix.join("drive_c").is_dir() {
+        report.diagnostics.push(Diagnostic::PrefixNotExists(prefix.to_path_buf()));
+
+        return;
+    }
+
+    let dosdevices = prefix.join("dosdevices");
+
+    let Ok(entries) = dosdevices.read_dir() else {
+        report.diagnostics.push(Diagnostic::DosdevicesMissing(dosdevices));
+
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let drive = entry.file_name().to_string_lossy().to_string();
+
+        // Only check drive letters. Other entries are devices like com1 or c::
+        if drive.len() != 2 || !drive.ends_with(':') {
+            continue;
+        }
+
+        let Ok(target) = std::fs::read_link(entry.path()) else {
+            continue;
+        };
+
+        // Relative links are resolved from the dosdevices folder
+        if !dosdevices.join(&target).exists() {
+            report.diagnostics.push(Diagnostic::BrokenDrive {
+                drive,
+                target
+            });
+        }
+    }
+}
+
+/// Check if there's at least one Vulkan ICD manifest
+fn has_vulkan_icd() -> bool {
+    // Manually specified ICD manifests
+    for var in ["VK_DRIVER_FILES", "VK_ICD_FILENAMES"] {
+        if let Ok(files) = std::env::var(var) {
+            if files.split(':').any(|file| Path::new(file).exists()) {
+                return true;
+            }
+        }
+    }
+
+    let mut folders = VULKAN_ICD_FOLDERS.iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+
+    if let Ok(dirs) = std::env::var("XDG_DATA_DIRS") {
+        folders.extend(dirs.split(':').map(|dir| Path::new(dir).join("vulkan/icd.d")));
+    }
+
+    if let Some(data) = dirs::data_dir() {
+        folders.push(data.join("vulkan/icd.d"));
+    }
+
+    folders.into_iter().any(|folder| {
+        folder.read_dir()
+            .map(|entries| entries.flatten().any(|entry| entry.path().extension().map(|ext| ext == "json").unwrap_or_default()))
+            .unwrap_or_default()
+    })
+}
@@ -41,6 +41,7 @@ pub struct Game {
     pub enhancements: Enhancements,
     pub environment: HashMap<String, String>,
     pub command: Option<String>,
+    pub hooks: Hooks,
     pub telemetry_ignored: bool
 }
 
@@ -57,6 +58,7 @@ impl Default for Game {
             enhancements: Enhancements::default(),
             environment: HashMap::new(),
             command: None,
+            hooks: Hooks::default(),
             telemetry_ignored: false
         }
     }
@@ -133,6 +135,10 @@ impl From<&JsonValue> for Game {
                 None => default.command
             },
 
+            hooks: value.get("hooks")
+                .map(Hooks::from)
+                .unwrap_or(default.hooks),
+
             telemetry_ignored: value.get("telemetry_ignored")
                 .and_then(JsonValue::as_bool)
                 .unwrap_or(default.telemetry_ignored)
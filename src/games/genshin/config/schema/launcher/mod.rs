@@ -64,6 +64,7 @@ pub struct Launcher {
     pub style: LauncherStyle,
     pub temp: Option<PathBuf>,
     pub repairer: Repairer,
+    pub logs: Logs,
 
     pub permissive: bool,
 
@@ -73,7 +74,10 @@ pub struct Launcher {
     #[cfg(feature = "environment-emulation")]
     pub environment: Environment,
 
-    pub behavior: LauncherBehavior
+    pub behavior: LauncherBehavior,
+
+    /// Name of the profile from `profiles` merged over the base config
+    pub active_profile: Option<String>
 }
 
 impl Default for Launcher {
@@ -85,6 +89,7 @@ impl Default for Launcher {
             style: LauncherStyle::default(),
             temp: launcher_dir().ok(),
             repairer: Repairer::default(),
+            logs: Logs::default(),
 
             permissive: steam::launched_from_steam(),
 
@@ -94,7 +99,8 @@ impl Default for Launcher {
             #[cfg(feature = "environment-emulation")]
             environment: Environment::default(),
 
-            behavior: LauncherBehavior::default()
+            behavior: LauncherBehavior::default(),
+            active_profile: None
         }
     }
 }
@@ -138,6 +144,11 @@ impl From<&JsonValue> for Launcher {
                 None => default.repairer
             },
 
+            logs: match value.get("logs") {
+                Some(value) => Logs::from(value),
+                None => default.logs
+            },
+
             permissive: match value.get("permissive") {
                 Some(value) => serde_json::from_value(value.to_owned()).unwrap_or_default(),
                 None => false
@@ -158,6 +169,11 @@ impl From<&JsonValue> for Launcher {
             behavior: match value.get("behavior") {
                 Some(value) => serde_json::from_value(value.clone()).unwrap_or(default.behavior),
                 None => default.behavior
+            },
+
+            active_profile: match value.get("active_profile") {
+                Some(value) => value.as_str().map(String::from),
+                None => default.active_profile
             }
         }
     }
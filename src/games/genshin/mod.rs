@@ -17,3 +17,9 @@ pub mod game;
 
 #[cfg(feature = "sessions")]
 pub mod sessions;
+
+#[cfg(feature = "stats")]
+pub mod stats;
+
+#[cfg(all(feature = "integrations", feature = "config"))]
+pub mod desktop;
@@ -6,6 +6,9 @@ use anime_game_core::genshin::prelude::*;
 use crate::config::ConfigExt;
 use crate::genshin::config::Config;
 
+#[cfg(feature = "diagnostics")]
+use crate::diagnostics::DiagnosticsReport;
+
 #[derive(Debug, Clone)]
 pub enum LauncherState {
     Launch,
@@ -27,8 +30,20 @@ pub enum LauncherState {
     #[cfg(feature = "components")]
     WineNotInstalled,
 
+    /// Wine prefix can be created using `Config::get_prefix_manager`
     PrefixNotExists,
 
+    #[cfg(feature = "components")]
+    /// Wine prefix was initialized or updated by another wine build
+    /// 
+    /// Contains name of that build. Prefix can be updated
+    /// using `PrefixManager::update` from `Config::get_prefix_manager`
+    PrefixUpdateRequired(String),
+
+    #[cfg(feature = "diagnostics")]
+    /// Preflight diagnostics found problems which will most likely break the game
+    DiagnosticsFailed(DiagnosticsReport),
+
     // Always contains `VersionDiff::Diff`
     VoiceUpdateAvailable(VersionDiff),
 
@@ -73,7 +88,7 @@ impl LauncherState {
         let config = Config::get()?;
 
         if let Some(wine) = config.get_selected_wine()? {
-            managed = wine.managed;
+            managed = wine.is_prefix_managed();
         }
         // Check prefix existence
         if !params.wine_prefix.join("drive_c").exists() && !managed {
@@ -164,18 +179,17 @@ impl LauncherState {
 
         match &config.game.wine.selected {
             #[cfg(feature = "components")]
-            Some(selected) => {
-                if !config.game.wine.builds.join(selected).exists() {
-                    match config.get_selected_wine()? {
-                        Some(selected) => {
-                            if !selected.managed {
-                                return Ok(Self::WineNotInstalled);
-                            }
-                        },
-                        None => {}
-                    }
+            Some(selected) if !config.game.wine.builds.join(selected).exists() => {
+                // Managed builds are stored outside of the builds folder
+                let installed = config.get_selected_wine()?
+                    .map(|wine| wine.get_runner_dir(&config.game.wine.builds).exists())
+                    .unwrap_or_default();
+
+                if !installed {
+                    return Ok(Self::WineNotInstalled);
                 }
             }
+
             None => return Ok(Self::WineNotInstalled),
 
             _ => ()
@@ -190,15 +204,33 @@ impl LauncherState {
             });
         }
 
-        Self::get(LauncherStateParams {
+        // Check if the prefix should be updated by the selected wine build
+        #[cfg(feature = "components")]
+        if let Some(runner) = config.get_outdated_prefix_runner()? {
+            return Ok(Self::PrefixUpdateRequired(runner));
+        }
+
+        let state = Self::get(LauncherStateParams {
             game_path: config.game.path.for_edition(config.launcher.edition).to_path_buf(),
             game_edition: config.launcher.edition,
 
-            wine_prefix: config.get_wine_prefix_path(),
+            wine_prefix: config.get_wine_prefix_check_path(),
             selected_voices: voices,
             telemetry_ignored: config.game.telemetry_ignored,
 
             status_updater
-        })
+        })?;
+
+        // Run diagnostics only when everything else is ready
+        #[cfg(feature = "diagnostics")]
+        if let Self::Launch = state {
+            let report = Config::get_resolved()?.diagnose();
+
+            if report.has_errors() {
+                return Ok(Self::DiagnosticsFailed(report));
+            }
+        }
+
+        Ok(state)
     }
 }
@@ -0,0 +1,93 @@
+use std::path::Path;
+
+use anime_game_core::honkai::consts::GameEdition;
+
+use crate::config::ConfigExt;
+use crate::honkai::config::Config;
+use crate::honkai::consts;
+
+use crate::integrations::desktop::{self, DesktopEntry, DesktopTarget};
+
+/// Game editions which can have their own desktop entries
+pub const EDITIONS: &[GameEdition] = &[
+    GameEdition::Global,
+    GameEdition::Sea,
+    GameEdition::China,
+    GameEdition::Taiwan,
+    GameEdition::Korea,
+    GameEdition::Japan
+];
+
+/// Name of the game shown in desktop entries
+pub const GAME_NAME: &str = "Honkers";
+
+/// Prefix of desktop entries and icons of the game
+#[inline]
+fn entries_prefix() -> String {
+    format!("{}-honkai-", consts::FOLDER_NAME)
+}
+
+#[inline]
+/// Get id of the edition's desktop entry and icon
+pub fn entry_id(edition: GameEdition) -> String {
+    format!("{}{}", entries_prefix(), format!("{edition:?}").to_lowercase())
+}
+
+/// Get desktop entry of the edition
+pub fn get_entry(launcher: impl AsRef<Path>, edition: GameEdition, target: DesktopTarget) -> anyhow::Result<DesktopEntry> {
+    let edition_name = format!("{edition:?}").to_lowercase();
+
+    let mut entry = DesktopEntry::new(
+        entry_id(edition),
+        format!("{GAME_NAME} ({edition:?})"),
+        desktop::launcher_command(launcher, consts::launcher_dir()?, target, Some(&edition_name))
+    );
+
+    entry.icon = Some(entry_id(edition));
+
+    Ok(entry)
+}
+
+/// Install desktop entries of all the installed editions
+/// 
+/// Edition is installed if its game folder exists.
+/// Given icon is installed into the hicolor icons theme for every edition
+#[tracing::instrument(level = "debug", ret)]
+pub fn install(launcher: &Path, icon: Option<&Path>, target: DesktopTarget) -> anyhow::Result<Vec<DesktopEntry>> {
+    let config = Config::get()?;
+
+    let mut entries = Vec::new();
+
+    for edition in EDITIONS.iter().copied() {
+        // Remove entries and icons of the editions which are not installed anymore
+        if !config.game.path.for_edition(edition).exists() {
+            desktop::uninstall_prefixed(&entry_id(edition))?;
+
+            continue;
+        }
+
+        let mut entry = get_entry(launcher, edition, target)?;
+
+        match icon {
+            Some(icon) => {
+                desktop::install_icon(entry_id(edition), icon)?;
+            }
+
+            None => entry.icon = None
+        }
+
+        entry.install()?;
+
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+#[inline]
+/// Remove desktop entries and icons of all the editions
+pub fn uninstall() -> anyhow::Result<()> {
+    desktop::uninstall_prefixed(&entries_prefix())?;
+
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use std::path::PathBuf;
+
+use crate::stats::StatsExt;
+
+use super::consts::launcher_dir;
+
+/// Get default stats file path
+/// 
+/// `$HOME/.local/share/honkers-launcher/stats.jsonl`
+#[inline]
+pub fn stats_file() -> anyhow::Result<PathBuf> {
+    launcher_dir().map(|dir| dir.join("stats.jsonl"))
+}
+
+pub struct Stats;
+
+impl StatsExt for Stats {
+    #[inline]
+    fn stats_file() -> anyhow::Result<PathBuf> {
+        stats_file()
+    }
+}
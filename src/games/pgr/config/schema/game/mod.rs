@@ -27,7 +27,8 @@ pub struct Game {
     pub dxvk: Dxvk,
     pub enhancements: Enhancements,
     pub environment: HashMap<String, String>,
-    pub command: Option<String>
+    pub command: Option<String>,
+    pub hooks: Hooks
 }
 
 impl Default for Game {
@@ -41,7 +42,8 @@ impl Default for Game {
             dxvk: Dxvk::default(),
             enhancements: Enhancements::default(),
             environment: HashMap::new(),
-            command: None
+            command: None,
+            hooks: Hooks::default()
         }
     }
 }
@@ -101,7 +103,11 @@ impl From<&JsonValue> for Game {
                     }
                 },
                 None => default.command
-            }
+            },
+
+            hooks: value.get("hooks")
+                .map(Hooks::from)
+                .unwrap_or(default.hooks)
         }
     }
 }
@@ -57,11 +57,15 @@ pub struct Launcher {
     pub style: LauncherStyle,
     pub temp: Option<PathBuf>,
     pub repairer: Repairer,
+    pub logs: Logs,
 
     #[cfg(feature = "discord-rpc")]
     pub discord_rpc: DiscordRpc,
 
-    pub behavior: LauncherBehavior
+    pub behavior: LauncherBehavior,
+
+    /// Name of the profile from `profiles` merged over the base config
+    pub active_profile: Option<String>
 }
 
 impl Default for Launcher {
@@ -72,11 +76,13 @@ impl Default for Launcher {
             style: LauncherStyle::default(),
             temp: launcher_dir().ok(),
             repairer: Repairer::default(),
+            logs: Logs::default(),
 
             #[cfg(feature = "discord-rpc")]
             discord_rpc: DiscordRpc::default(),
 
-            behavior: LauncherBehavior::default()
+            behavior: LauncherBehavior::default(),
+            active_profile: None
         }
     }
 }
@@ -115,6 +121,11 @@ impl From<&JsonValue> for Launcher {
                 None => default.repairer
             },
 
+            logs: match value.get("logs") {
+                Some(value) => Logs::from(value),
+                None => default.logs
+            },
+
             #[cfg(feature = "discord-rpc")]
             discord_rpc: match value.get("discord_rpc") {
                 Some(value) => DiscordRpc::from(value),
@@ -124,6 +135,11 @@ impl From<&JsonValue> for Launcher {
             behavior: match value.get("behavior") {
                 Some(value) => serde_json::from_value(value.clone()).unwrap_or(default.behavior),
                 None => default.behavior
+            },
+
+            active_profile: match value.get("active_profile") {
+                Some(value) => value.as_str().map(String::from),
+                None => default.active_profile
             }
         }
     }
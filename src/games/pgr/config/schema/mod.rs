@@ -1,20 +1,31 @@
 use std::path::PathBuf;
+use std::collections::HashMap;
 
 use serde::{Serialize, Deserialize};
 use serde_json::Value as JsonValue;
 
 use wincompatlib::prelude::*;
 
+use crate::config::schema_blanks::profile::Profile;
+
+#[cfg(feature = "diagnostics")]
+use crate::diagnostics::{DiagnosticsParams, DiagnosticsReport};
+
 #[cfg(feature = "sandbox")]
 use crate::config::schema_blanks::sandbox::Sandbox;
 
 #[cfg(feature = "components")]
 use crate::components::{
+    loader::ComponentsLoader,
     wine::{
         UnifiedWine,
         Version as WineVersion
     },
-    dxvk::Version as DxvkVersion
+    dxvk::Version as DxvkVersion,
+    prefix::{PrefixManager, COREFONTS, get_prefix_runner},
+    backups::PrefixBackups,
+    templates::PrefixTemplates,
+    overlay::{PrefixOverlay, OverlayMount}
 };
 
 pub mod launcher;
@@ -40,6 +51,7 @@ use prelude::*;
 pub struct Schema {
     pub launcher: Launcher,
     pub game: Game,
+    pub profiles: HashMap<String, Profile>,
 
     #[cfg(feature = "sandbox")]
     pub sandbox: Sandbox,
@@ -63,6 +75,14 @@ impl From<&JsonValue> for Schema {
                 None => default.game
             },
 
+            profiles: match value.get("profiles").and_then(JsonValue::as_object) {
+                Some(values) => values.iter()
+                    .map(|(name, value)| (name.clone(), Profile::from(value)))
+                    .collect(),
+
+                None => default.profiles
+            },
+
             #[cfg(feature = "sandbox")]
             sandbox: match value.get("sandbox") {
                 Some(value) => Sandbox::from(value),
@@ -79,19 +99,80 @@ impl From<&JsonValue> for Schema {
 }
 
 impl Schema {
+    /// Get profile selected by `launcher.active_profile`
+    pub fn get_active_profile(&self) -> Option<&Profile> {
+        let name = self.launcher.active_profile.as_ref()?;
+
+        let profile = self.profiles.get(name);
+
+        if profile.is_none() {
+            tracing::warn!("Active profile '{name}' doesn't exist. Using base config");
+        }
+
+        profile
+    }
+
+    /// Get config with the active profile merged over the base values
+    pub fn resolve(&self) -> Self {
+        let mut schema = self.clone();
+
+        if let Some(profile) = self.get_active_profile() {
+            if let Some(gamescope) = &profile.gamescope {
+                schema.game.enhancements.gamescope = Profile::merge(&schema.game.enhancements.gamescope, gamescope);
+            }
+
+            if let Some(hud) = &profile.hud {
+                schema.game.enhancements.hud = Profile::merge(&schema.game.enhancements.hud, hud);
+            }
+
+            if let Some(fsr) = &profile.fsr {
+                schema.game.enhancements.fsr = Profile::merge(&schema.game.enhancements.fsr, fsr);
+            }
+
+            if let Some(gamemode) = profile.gamemode {
+                schema.game.enhancements.gamemode = gamemode;
+            }
+
+            if let Some(sync) = profile.sync {
+                schema.game.wine.sync = sync;
+            }
+
+            schema.game.environment.extend(profile.environment.clone());
+
+            if let Some(command) = &profile.command {
+                schema.game.command = Some(command.clone());
+            }
+        }
+
+        schema
+    }
+
+    #[cfg(feature = "components")]
+    /// Get components loader with custom wine builds from the config
+    /// 
+    /// Use it to list wine versions, so the custom builds are listed too
+    pub fn get_components_loader(&self) -> ComponentsLoader {
+        ComponentsLoader::new(&self.components.path)
+            .with_custom(self.components.custom.iter().cloned())
+    }
+
     #[cfg(feature = "components")]
     /// Get selected wine version
     pub fn get_selected_wine(&self) -> anyhow::Result<Option<WineVersion>> {
         match &self.game.wine.selected {
-            Some(selected) => WineVersion::find_in(&self.components.path, selected),
+            Some(selected) => self.get_components_loader().find_wine_version(selected),
             None => Ok(None)
         }
     }
 
     #[cfg(feature = "components")]
     /// Get selected dxvk version
+    /// 
+    /// Prefix overlay is mounted while its DXVK version is read
     pub fn get_selected_dxvk(&self) -> anyhow::Result<Option<DxvkVersion>> {
-        match wincompatlib::dxvk::Dxvk::get_version(&self.game.wine.prefix)? {
+        let _overlay = self.mount_prefix_overlay()?;
+
+        match wincompatlib::dxvk::Dxvk::get_version(self.get_real_prefix_path(&self.game.wine.prefix))? {
             Some(version) => DxvkVersion::find_in(&self.components.path, version),
             None => Ok(None)
         }
@@ -102,20 +183,212 @@ impl Schema {
     /// 
     /// - For general wine build returns `game.wine.prefix`
     /// - For proton-like builds return `game.wine.prefix`/`pfx`
-    pub fn get_wine_prefix_path(&self) -> PathBuf {
+    /// 
+    /// If the prefix overlay is enabled, `game.wine.prefix` is the mount point
+    /// of its merged view. It's mounted only while it's used, so this function
+    /// fails if it's not mounted. Use `mount_prefix_overlay` to mount it
+    pub fn get_wine_prefix_path(&self) -> anyhow::Result<PathBuf> {
+        if let Some(overlay) = self.get_prefix_overlay() {
+            if !overlay.is_mounted() {
+                anyhow::bail!("Wine prefix overlay is not mounted: {:?}", overlay.merged);
+            }
+        }
+
+        Ok(self.get_real_prefix_path(&self.game.wine.prefix))
+    }
+
+    #[cfg(feature = "components")]
+    /// Get real wine prefix path which can be used to check if the prefix exists
+    /// 
+    /// If the prefix overlay is enabled and not mounted, then its base prefix
+    /// is returned. Files of the base prefix must not be changed
+    pub(crate) fn get_wine_prefix_check_path(&self) -> PathBuf {
+        match self.get_prefix_overlay() {
+            Some(overlay) if !overlay.is_mounted() => self.get_real_prefix_path(overlay.base),
+            _ => self.get_real_prefix_path(&self.game.wine.prefix)
+        }
+    }
+
+    #[cfg(feature = "components")]
+    /// Resolve real wine prefix path of the given prefix folder using wincompatlib
+    /// 
+    /// For proton-like builds returns `prefix`/`pfx`
+    pub fn get_real_prefix_path(&self, prefix: impl Into<PathBuf>) -> PathBuf {
+        let prefix = prefix.into();
+
         if let Ok(Some(wine)) = self.get_selected_wine() {
             let wine = wine
-                .to_wine(&self.components.path, Some(&self.game.wine.builds.join(&wine.name)))
-                .with_prefix(&self.game.wine.prefix);
+                .to_wine(&self.components.path, Some(&wine.get_runner_dir(&self.game.wine.builds)))
+                .with_prefix(&prefix);
 
-            let prefix = match wine {
+            return match wine {
                 UnifiedWine::Default(wine) => wine.prefix,
                 UnifiedWine::Proton(proton) => proton.wine().prefix.clone()
             };
+        }
+
+        prefix
+    }
 
-            return prefix;
+    #[cfg(feature = "components")]
+    /// Get manager of the wine prefix using selected wine version
+    /// 
+    /// Returns `None` if wine is not selected or its prefix is managed by Steam.
+    /// Given DXVK version is installed from the `game.dxvk.builds` folder
+    /// unless the wine is a proton build which has its own DXVK.
+    /// 
+    /// Prefix is cloned from the selected wine's template if it was built.
+    /// If the prefix overlay is enabled and its base prefix doesn't exist,
+    /// then the manager creates the base prefix. Otherwise the manager changes
+    /// the overlay's merged view, which is mounted only while it's changed
+    pub fn get_prefix_manager(&self, dxvk: Option<&DxvkVersion>) -> anyhow::Result<Option<PrefixManager>> {
+        let Some(wine) = self.get_selected_wine()? else {
+            return Ok(None);
+        };
+
+        if wine.is_prefix_managed() {
+            return Ok(None);
+        }
+
+        let templates = self.get_prefix_templates()?;
+        let template = templates.exists(&wine.name).then(|| templates.template(&wine.name));
+
+        let name = wine.name.clone();
+
+        let wine = wine.to_wine(&self.components.path, Some(&wine.get_runner_dir(&self.game.wine.builds)));
+        let is_proton = matches!(wine, UnifiedWine::Proton(_));
+
+        let overlay = self.get_prefix_overlay();
+
+        let prefix = match &overlay {
+            Some(overlay) if !overlay.base.exists() => overlay.base.clone(),
+            _ => self.game.wine.prefix.clone()
+        };
+
+        let mut manager = PrefixManager::new(wine, prefix)
+            .with_runner(name)
+            .with_fonts(COREFONTS.iter().copied())
+            .with_drives(&self.game.wine.drives, &self.game.path);
+
+        if let Some(template) = template {
+            manager = manager.with_template(template);
+        }
+
+        if let Some(overlay) = overlay.filter(|overlay| overlay.base.exists()) {
+            manager = manager.with_overlay(overlay);
+        }
+
+        if let Some(dxvk) = dxvk {
+            if !is_proton {
+                manager = manager.with_dxvk(self.game.dxvk.builds.join(&dxvk.name), InstallParams::default())
+                    .with_dxvk_version(&dxvk.version);
+            }
+        }
+
+        Ok(Some(manager))
+    }
+
+    #[cfg(feature = "components")]
+    /// Check if the wine prefix was initialized or updated by another wine build
+    /// 
+    /// Returns name of that build. Build is recorded when the prefix is created,
+    /// updated or used to run the game, so prefixes which don't have it recorded yet,
+    /// and prefixes of the Steam managed builds are not reported
+    pub fn get_outdated_prefix_runner(&self) -> anyhow::Result<Option<String>> {
+        if crate::integrations::steam::is_prefix_update_disabled() {
+            return Ok(None);
+        }
+
+        let Some(wine) = self.get_selected_wine()? else {
+            return Ok(None);
+        };
+
+        if wine.is_prefix_managed() {
+            return Ok(None);
+        }
+
+        // Overlay is mounted only while it's used, so the changes folder
+        // has the runner recorded by the last run
+        let runner = match self.get_prefix_overlay() {
+            Some(overlay) if !overlay.is_mounted() => get_prefix_runner(&overlay.upper)
+                .or_else(|| get_prefix_runner(&overlay.base)),
+
+            _ => get_prefix_runner(&self.game.wine.prefix)
+        };
+
+        match runner {
+            Some(runner) if runner != wine.name => Ok(Some(runner)),
+            _ => Ok(None)
         }
+    }
+
+    #[cfg(feature = "components")]
+    /// Get storage of the wine prefix backups
+    /// 
+    /// Backups are stored in the `backups` folder of the launcher.
+    /// It's recommended to create one before switching wine builds.
+    /// 
+    /// If the prefix overlay is enabled, its merged view is backed up
+    pub fn get_prefix_backups(&self) -> anyhow::Result<PrefixBackups> {
+        let mut backups = PrefixBackups::new(crate::pgr::consts::launcher_dir()?.join("backups"), &self.game.wine.prefix);
+
+        if let Some(overlay) = self.get_prefix_overlay() {
+            backups = backups.with_overlay(overlay);
+        }
+
+        Ok(backups)
+    }
+
+    #[cfg(feature = "components")]
+    /// Get storage of the wine prefix templates
+    /// 
+    /// Templates are built using `PrefixTemplates::build`
+    /// with the manager from `get_prefix_manager`
+    pub fn get_prefix_templates(&self) -> anyhow::Result<PrefixTemplates> {
+        Ok(PrefixTemplates::new(crate::pgr::consts::launcher_dir()?.join("templates")))
+    }
+
+    #[cfg(feature = "components")]
+    /// Get wine prefix overlay if it's enabled
+    /// 
+    /// Overlay's merged view is mounted to the `game.wine.prefix` folder
+    pub fn get_prefix_overlay(&self) -> Option<PrefixOverlay> {
+        self.game.wine.overlay.enabled
+            .then(|| PrefixOverlay::new(&self.game.wine.overlay.base, &self.game.wine.prefix))
+    }
+
+    #[cfg(feature = "components")]
+    /// Mount the wine prefix overlay if it's enabled
+    /// 
+    /// Overlay is unmounted when the returned guard is dropped,
+    /// so it should be kept while the prefix is used
+    pub fn mount_prefix_overlay(&self) -> anyhow::Result<Option<OverlayMount>> {
+        self.get_prefix_overlay()
+            .map(|overlay| overlay.mount())
+            .transpose()
+    }
+
+    #[cfg(feature = "diagnostics")]
+    /// Check everything needed to run the game
+    pub fn diagnose(&self) -> DiagnosticsReport {
+        crate::diagnostics::diagnose(&DiagnosticsParams {
+            components: self.components.path.clone(),
+            custom_wine: self.components.custom.clone(),
+            wine: self.game.wine.selected.clone(),
+            wine_builds: self.game.wine.builds.clone(),
+            prefix: self.get_wine_prefix_check_path(),
+
+            gamescope: self.game.enhancements.gamescope.enabled,
+            gamemode: self.game.enhancements.gamemode,
+            mangohud: self.game.enhancements.hud == crate::config::schema_blanks::prelude::HUD::MangoHUD,
+
+            #[cfg(feature = "sandbox")]
+            sandbox: self.sandbox.enabled,
+
+            #[cfg(not(feature = "sandbox"))]
+            sandbox: false,
 
-        self.game.wine.prefix.clone()
+            overlay: self.game.wine.overlay.enabled
+        })
     }
 }
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use crate::config::ConfigExt;
+use crate::pgr::config::Config;
+use crate::pgr::consts;
+
+use crate::integrations::desktop::{self, DesktopEntry, DesktopTarget};
+
+/// Name of the game shown in desktop entries
+pub const GAME_NAME: &str = "Punishing: Gray Raven";
+
+#[inline]
+/// Get id of the game's desktop entry and icon
+pub fn entry_id() -> String {
+    format!("{}-pgr", consts::FOLDER_NAME)
+}
+
+/// Get desktop entry of the game
+pub fn get_entry(launcher: impl AsRef<Path>, target: DesktopTarget) -> anyhow::Result<DesktopEntry> {
+    let mut entry = DesktopEntry::new(
+        entry_id(),
+        GAME_NAME,
+        desktop::launcher_command(launcher, consts::launcher_dir()?, target, None)
+    );
+
+    entry.icon = Some(entry_id());
+
+    Ok(entry)
+}
+
+/// Install desktop entry of the game if it's installed
+/// 
+/// Given icon is installed into the hicolor icons theme
+#[tracing::instrument(level = "debug", ret)]
+pub fn install(launcher: &Path, icon: Option<&Path>, target: DesktopTarget) -> anyhow::Result<Option<DesktopEntry>> {
+    let config = Config::get()?;
+
+    // Remove the entry and icons if the game is not installed anymore
+    if !config.game.path.exists() {
+        uninstall()?;
+
+        return Ok(None);
+    }
+
+    let mut entry = get_entry(launcher, target)?;
+
+    match icon {
+        Some(icon) => {
+            desktop::install_icon(entry_id(), icon)?;
+        }
+
+        None => entry.icon = None
+    }
+
+    entry.install()?;
+
+    Ok(Some(entry))
+}
+
+#[inline]
+/// Remove desktop entry and icons of the game
+pub fn uninstall() -> anyhow::Result<()> {
+    desktop::uninstall_prefixed(&entry_id())?;
+
+    Ok(())
+}
@@ -7,6 +7,9 @@ use anime_game_core::pgr::prelude::*;
 
 use crate::config::ConfigExt;
 
+#[cfg(feature = "diagnostics")]
+use crate::diagnostics::DiagnosticsReport;
+
 #[derive(Debug, Clone)]
 pub enum LauncherState {
     Launch,
@@ -14,8 +17,20 @@ pub enum LauncherState {
     #[cfg(feature = "components")]
     WineNotInstalled,
 
+    /// Wine prefix can be created using `Config::get_prefix_manager`
     PrefixNotExists,
 
+    #[cfg(feature = "components")]
+    /// Wine prefix was initialized or updated by another wine build
+    /// 
+    /// Contains name of that build. Prefix can be updated
+    /// using `PrefixManager::update` from `Config::get_prefix_manager`
+    PrefixUpdateRequired(String),
+
+    #[cfg(feature = "diagnostics")]
+    /// Preflight diagnostics found problems which will most likely break the game
+    DiagnosticsFailed(DiagnosticsReport),
+
     Mfc140NotInstalled,
     FontsNotInstalled(Vec<Font>),
 
@@ -108,19 +123,46 @@ impl LauncherState {
 
         match &config.game.wine.selected {
             #[cfg(feature = "components")]
-            Some(selected) if !config.game.wine.builds.join(selected).exists() => return Ok(Self::WineNotInstalled),
+            Some(selected) if !config.game.wine.builds.join(selected).exists() => {
+                // Managed builds are stored outside of the builds folder
+                let installed = config.get_selected_wine()?
+                    .map(|wine| wine.get_runner_dir(&config.game.wine.builds).exists())
+                    .unwrap_or_default();
+
+                if !installed {
+                    return Ok(Self::WineNotInstalled);
+                }
+            }
 
             None => return Ok(Self::WineNotInstalled),
 
             _ => ()
         }
 
-        Self::get(LauncherStateParams {
-            wine_prefix: config.get_wine_prefix_path(),
+        // Check if the prefix should be updated by the selected wine build
+        #[cfg(feature = "components")]
+        if let Some(runner) = config.get_outdated_prefix_runner()? {
+            return Ok(Self::PrefixUpdateRequired(runner));
+        }
+
+        let state = Self::get(LauncherStateParams {
+            wine_prefix: config.get_wine_prefix_check_path(),
             game_path: config.game.path,
             fast_verify: config.launcher.repairer.fast,
 
             status_updater
-        })
+        })?;
+
+        // Run diagnostics only when everything else is ready
+        #[cfg(feature = "diagnostics")]
+        if let Self::Launch = state {
+            let report = crate::pgr::config::Config::get_resolved()?.diagnose();
+
+            if report.has_errors() {
+                return Ok(Self::DiagnosticsFailed(report));
+            }
+        }
+
+        Ok(state)
     }
 }
@@ -39,6 +39,11 @@ impl ConfigExt for Config {
         schema.clone()
     }
 
+    #[inline]
+    fn resolve_schema(schema: &Self::Schema) -> Self::Schema {
+        schema.resolve()
+    }
+
     #[inline]
     fn get() -> anyhow::Result<Self::Schema> {
         unsafe {
@@ -3,12 +3,17 @@ use std::path::PathBuf;
 use serde::{Serialize, Deserialize};
 use serde_json::Value as JsonValue;
 
+use crate::components::wine::CustomWine;
+
 use crate::star_rail::consts::launcher_dir;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Components {
     pub path: PathBuf,
-    pub servers: Vec<String>
+    pub servers: Vec<String>,
+
+    /// Wine builds from the local folders
+    pub custom: Vec<CustomWine>
 }
 
 impl Default for Components {
@@ -20,7 +25,8 @@ impl Default for Components {
             path: launcher_dir.join("components"),
             servers: vec![
                 String::from("https://github.com/an-anime-team/components")
-            ]
+            ],
+            custom: Vec::new()
         }
     }
 }
@@ -54,6 +60,15 @@ impl From<&JsonValue> for Components {
                     None => default.servers
                 },
                 None => default.servers
+            },
+
+            custom: match value.get("custom").and_then(|value| value.as_array()) {
+                Some(values) => values.iter()
+                    .map(CustomWine::from)
+                    .filter(|wine| !wine.name.is_empty())
+                    .collect(),
+
+                None => default.custom
             }
         }
     }
@@ -31,7 +31,8 @@ pub struct Game {
     pub dxvk: Dxvk,
     pub enhancements: Enhancements,
     pub environment: HashMap<String, String>,
-    pub command: Option<String>
+    pub command: Option<String>,
+    pub hooks: Hooks
 }
 
 impl Default for Game {
@@ -46,7 +47,8 @@ impl Default for Game {
             dxvk: Dxvk::default(),
             enhancements: Enhancements::default(),
             environment: HashMap::new(),
-            command: None
+            command: None,
+            hooks: Hooks::default()
         }
     }
 }
@@ -120,7 +122,11 @@ impl From<&JsonValue> for Game {
                     }
                 },
                 None => default.command
-            }
+            },
+
+            hooks: value.get("hooks")
+                .map(Hooks::from)
+                .unwrap_or(default.hooks)
         }
     }
 }
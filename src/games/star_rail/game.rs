@@ -1,26 +1,51 @@
-use std::process::{Command, Stdio};
 use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
 
+use wincompatlib::prelude::*;
+
+use anime_game_core::prelude::*;
 use anime_game_core::star_rail::telemetry;
+use anime_game_core::star_rail::game::Game;
 
 use crate::components::wine::Bundle as WineBundle;
 
 use crate::config::ConfigExt;
 use crate::star_rail::config::Config;
 
-use crate::config::schema_blanks::prelude::{
-    WineDrives,
-    AllowedDrives
+use crate::config::schema_blanks::prelude::AllowedDrives;
+
+use crate::launch::{
+    LaunchPlan,
+    LaunchCommand,
+    LaunchStep,
+    LaunchEvent,
+    LaunchEvents,
+    LaunchLogs,
+    GeneratedFiles,
+    PrefixLock,
+    WineserverCleanup
 };
 
+use crate::shell;
+use crate::template::{Keywords, Quoting};
+
 use crate::star_rail::consts;
 
 #[cfg(feature = "discord-rpc")]
-use crate::discord_rpc::*;
+use crate::{
+    discord_rpc::DiscordRpc,
+    launch::subscribers::DiscordRpcSubscriber
+};
+
+#[cfg(feature = "stats")]
+use crate::{
+    launch::subscribers::StatsSubscriber,
+    star_rail::stats::Stats
+};
 
 #[cfg(feature = "sessions")]
 use crate::{
-    sessions::SessionsExt,
+    launch::subscribers::SessionsSubscriber,
     star_rail::sessions::Sessions
 };
 
@@ -33,24 +58,30 @@ struct Folders {
     pub temp: PathBuf
 }
 
-fn replace_keywords(command: impl ToString, folders: &Folders) -> String {
-    command.to_string()
-        .replace("%build%", folders.wine.to_str().unwrap())
-        .replace("%prefix%", folders.prefix.to_str().unwrap())
-        .replace("%temp%", folders.game.to_str().unwrap())
-        .replace("%launcher%", &consts::launcher_dir().unwrap().to_string_lossy())
-        .replace("%game%", folders.temp.to_str().unwrap())
-        .replace("%patch%", folders.patch.to_str().unwrap())
+impl Folders {
+    /// Get keywords of these folders merged with the given ones
+    fn keywords(&self, keywords: &Keywords) -> anyhow::Result<Keywords> {
+        let mut keywords = keywords.clone();
+
+        keywords.merge(&Keywords::with_folders(&self.wine, &self.prefix, &self.game, &self.temp, consts::launcher_dir()?));
+
+        keywords.set_path("patch", &self.patch);
+
+        Ok(keywords)
+    }
 }
 
-/// Try to run the game
+/// Get the game launching plan
 /// 
-/// This function will freeze thread it was called from while the game is running
-#[tracing::instrument(level = "info", ret)]
-pub fn run() -> anyhow::Result<()> {
-    tracing::info!("Preparing to run the game");
-
-    let config = Config::get()?;
+/// This function doesn't write any files or spawn any processes,
+/// so it can be used to show what exactly will be executed by the `run` function
+// Plan is not logged here because it contains
+// environment variables which can be secret
+#[tracing::instrument(level = "info")]
+pub fn plan() -> anyhow::Result<LaunchPlan> {
+    tracing::info!("Preparing game launching plan");
+
+    let config = Config::get_resolved()?;
     let game_path = config.game.path.for_edition(config.launcher.edition).to_path_buf();
 
     if !game_path.exists() {
@@ -64,90 +95,160 @@ pub fn run() -> anyhow::Result<()> {
     let features = wine.features(&config.components.path)?.unwrap_or_default();
 
     let mut folders = Folders {
-        wine: config.game.wine.builds.join(&wine.name),
-        prefix: config.game.wine.prefix.clone(),
+        wine: wine.get_runner_dir(config.game.wine.builds.clone()),
+        prefix: wine.get_prefix_dir(config.game.wine.prefix.clone()),
         game: game_path.clone(),
         patch: config.patch.path.clone(),
         temp: config.launcher.temp.clone().unwrap_or(std::env::temp_dir())
     };
 
-    // Check telemetry servers
+    // Keywords which don't depend on the folders
 
-    tracing::info!("Checking telemetry");
+    let mut info = Keywords::new();
 
-    if let Ok(Some(server)) = telemetry::is_disabled(config.launcher.edition) {
-        return Err(anyhow::anyhow!("Telemetry server is not disabled: {server}"));
+    info.set("edition", format!("{:?}", config.launcher.edition))
+        .set("wine_name", &wine.name);
+
+    match Game::new(&folders.game, config.launcher.edition).get_version() {
+        Ok(version) => {
+            info.set("game_version", version);
+        }
+
+        Err(err) => tracing::warn!("Failed to get game version: {err}")
+    }
+
+    let mut keywords = folders.keywords(&info)?;
+
+    // We use real current dir here because sandboxed one
+    // obviously doesn't exist
+    let mut plan = LaunchPlan::new(&game_path, &folders.prefix);
+
+    // Proton builds store the real prefix in the `pfx` folder
+    plan.wine_prefix = config.get_real_prefix_path(&folders.prefix);
+
+    // Mount the shared base prefix overlay before preparing the prefix
+    // and record the selected wine build in it
+
+    if !wine.is_prefix_managed() {
+        plan.overlay = config.get_prefix_overlay();
+        plan.runner = Some(wine.name.clone());
+    }
+
+    // Stop leftover wine processes after the game exit
+    // Wineserver of the sandboxed game can't be reached from here
+
+    if config.game.wine.wineserver.enabled && !config.sandbox.enabled {
+        let wineserver = match &wine.files.wineserver {
+            Some(wineserver) => folders.wine.join(wineserver),
+
+            None if features.bundle == Some(WineBundle::Proton) => folders.wine.join("files/bin/wineserver"),
+            None => folders.wine.join("bin/wineserver")
+        };
+
+        plan.wineserver = Some(WineserverCleanup {
+            wine: wine.to_wine(&config.components.path, Some(&folders.wine)).with_prefix(&folders.prefix),
+            wineserver,
+            prefix: plan.wine_prefix.clone(),
+            params: config.game.wine.wineserver
+        });
     }
 
     // Prepare wine prefix drives
 
-    config.game.wine.drives.map_folders(&folders.game, &folders.prefix)?;
+    plan.drives = config.game.wine.drives.get_drives(&folders.game, &plan.wine_prefix);
 
     // Workaround for the jadeite patch (we run it from Z: drive)
-    WineDrives::map_folder(&folders.prefix, AllowedDrives::Z, "/")?;
+    plan.drives.insert(AllowedDrives::Z, PathBuf::from("/"));
 
     // Workaround for sandboxing feature
     if config.sandbox.enabled {
-        WineDrives::map_folder(&folders.prefix, AllowedDrives::C, "../drive_c")?;
+        plan.drives.insert(AllowedDrives::C, PathBuf::from("../drive_c"));
     }
 
-    // Prepare bash -c '<command>'
+    // Prepare hooks using real folders because they're executed outside of the sandbox
+
+    plan.hooks = config.game.hooks.clone();
+    plan.keywords = keywords.clone();
+
+    // Prepare the launching command
     // %command% = %bash_command% %windows_command% %launch_args%
 
-    let mut bash_command = String::new();
-    let mut windows_command = String::new();
-    let mut launch_args = String::new();
+    let mut command = LaunchCommand::default();
 
-    if config.game.enhancements.gamemode {
-        bash_command += "gamemoderun ";
-    }
+    command.runner = match features.command {
+        // Keywords are replaced after splitting because
+        // they're often quoted in the runner command
+        Some(runner) => shell::split(runner)?
+            .into_iter()
+            .map(|arg| keywords.render(arg, Quoting::Raw))
+            .collect::<Result<_, _>>()?,
 
-    let run_command = features.command
-        .map(|command| replace_keywords(command, &folders))
-        .unwrap_or(format!("'{}'", folders.wine.join(wine.files.wine64.unwrap_or(wine.files.wine)).to_string_lossy()));
+        None => vec![folders.wine.join(wine.files.wine64.unwrap_or(wine.files.wine)).to_string_lossy().to_string()]
+    };
 
-    bash_command += &run_command;
-    bash_command += " ";
+    if config.game.enhancements.gamemode {
+        command.wrap(["gamemoderun"]);
+    }
 
-    if let Some(virtual_desktop) = config.game.wine.virtual_desktop.get_command("star_rail") {
-        windows_command += &virtual_desktop;
-        windows_command += " ";
+    if let Some(virtual_desktop) = config.game.wine.virtual_desktop.get_args("star_rail") {
+        command.windows_args(virtual_desktop);
     }
 
-    windows_command += &format!("'{}/jadeite.exe' 'Z:\\{}/StarRail.exe' -- ", folders.patch.to_string_lossy(), folders.game.to_string_lossy());
+    command.windows_args([
+        format!("{}/jadeite.exe", folders.patch.to_string_lossy()),
+        format!("Z:\\{}/StarRail.exe", folders.game.to_string_lossy()),
+        String::from("--")
+    ]);
 
     if config.game.wine.borderless {
-        launch_args += "-screen-fullscreen 0 -popupwindow ";
+        command.launch_args(["-screen-fullscreen", "0", "-popupwindow"]);
     }
 
     // https://notabug.org/Krock/dawn/src/master/TWEAKS.md
     if config.game.enhancements.fsr.enabled {
-        launch_args += "-window-mode exclusive ";
+        command.launch_args(["-window-mode", "exclusive"]);
     }
 
     // gamescope <params> -- <command to run>
-    if let Some(gamescope) = config.game.enhancements.gamescope.get_command() {
-        bash_command = format!("{gamescope} -- {bash_command}");
+    if let Some(mut gamescope) = config.game.enhancements.gamescope.get_args() {
+        gamescope.push(String::from("--"));
+
+        command.wrap(gamescope);
     }
 
     // Bundle all windows arguments used to run the game into a single file
     if features.compact_launch {
-        std::fs::write(folders.game.join("compact_launch.bat"), format!("start {windows_command} {launch_args}\nexit"))?;
+        plan.file(folders.game.join("compact_launch.bat"), command.compact_launch_script());
 
-        windows_command = String::from("compact_launch.bat");
-        launch_args = String::new();
+        command.windows_command = vec![String::from("compact_launch.bat")];
+        command.launch_args.clear();
     }
 
     // bwrap <params> -- <command to run>
     #[cfg(feature = "sandbox")]
     if config.sandbox.enabled {
-        let bwrap = config.sandbox.get_command(
-            folders.wine.to_str().unwrap(),
-            folders.prefix.to_str().unwrap(),
-            folders.game.to_str().unwrap()
-        );
+        // Two overlays can't share the same changes folder,
+        // so bwrap mounts the overlay only if it's not mounted yet
+        let mut bwrap = match &plan.overlay {
+            Some(overlay) if !overlay.is_mounted() => {
+                // Prefix drives are mapped in the overlay's changes folder
+                plan.prefix = overlay.upper.clone();
+                plan.wine_prefix = config.get_real_prefix_path(&overlay.upper);
+                plan.sandbox_overlay = true;
+
+                config.sandbox.get_args_with_prefix(&folders.wine, overlay.bwrap_args("/tmp/sandbox/prefix"), &folders.game)
+            }
+
+            _ => config.sandbox.get_args(&folders.wine, &folders.prefix, &folders.game)
+        };
 
-        let bwrap = format!("{bwrap} --bind '{}' /tmp/sandbox/patch", folders.patch.to_string_lossy());
+        bwrap.extend([
+            String::from("--bind"),
+            folders.patch.to_string_lossy().to_string(),
+            String::from("/tmp/sandbox/patch")
+        ]);
+
+        bwrap.extend(["--chdir", "/tmp/sandbox/game", "--"].map(String::from));
 
         let sandboxed_folders = Folders {
             wine: PathBuf::from("/tmp/sandbox/wine"),
@@ -157,57 +258,52 @@ pub fn run() -> anyhow::Result<()> {
             temp: PathBuf::from("/tmp")
         };
 
-        bash_command = bash_command
-            .replace(folders.wine.to_str().unwrap(), sandboxed_folders.wine.to_str().unwrap())
-            .replace(folders.prefix.to_str().unwrap(), sandboxed_folders.prefix.to_str().unwrap())
-            .replace(folders.game.to_str().unwrap(), sandboxed_folders.game.to_str().unwrap())
-            .replace(folders.patch.to_str().unwrap(), sandboxed_folders.patch.to_str().unwrap())
-            .replace(folders.temp.to_str().unwrap(), sandboxed_folders.temp.to_str().unwrap());
-
-        windows_command = windows_command
-            .replace(folders.wine.to_str().unwrap(), sandboxed_folders.wine.to_str().unwrap())
-            .replace(folders.prefix.to_str().unwrap(), sandboxed_folders.prefix.to_str().unwrap())
-            .replace(folders.game.to_str().unwrap(), sandboxed_folders.game.to_str().unwrap())
-            .replace(folders.patch.to_str().unwrap(), sandboxed_folders.patch.to_str().unwrap())
-            .replace(folders.temp.to_str().unwrap(), sandboxed_folders.temp.to_str().unwrap());
-
-        bash_command = format!("{bwrap} --chdir /tmp/sandbox/game -- {bash_command}");
+        command.map_args(|arg| arg
+            .replace(&*folders.wine.to_string_lossy(), &sandboxed_folders.wine.to_string_lossy())
+            .replace(&*folders.prefix.to_string_lossy(), &sandboxed_folders.prefix.to_string_lossy())
+            .replace(&*folders.game.to_string_lossy(), &sandboxed_folders.game.to_string_lossy())
+            .replace(&*folders.patch.to_string_lossy(), &sandboxed_folders.patch.to_string_lossy())
+            .replace(&*folders.temp.to_string_lossy(), &sandboxed_folders.temp.to_string_lossy()));
+
+        command.wrap(bwrap);
+
         folders = sandboxed_folders;
+        keywords = folders.keywords(&info)?;
     }
 
     // Finalize launching command
-    bash_command = match &config.game.command {
+    plan.args = match &config.game.command {
         // Use user-given launch command
-        Some(command) => replace_keywords(command, &folders)
-            .replace("%command%", &format!("{bash_command} {windows_command}"))
-            .replace("%bash_command%", &bash_command)
-            .replace("%windows_command%", &windows_command)
-            .replace("%launch_args%", &launch_args),
-
-        // Combine bash and windows parts of the command
-        None => format!("{bash_command} {windows_command} {launch_args}")
+        Some(template) => command.with_template(template, keywords.clone())?,
+
+        // Run the command directly
+        None => command.to_args()
     };
 
-    let mut command = Command::new("bash");
+    // Store game output in a separate log file
+
+    plan.logs = Some(LaunchLogs::new(consts::launcher_dir()?, "star_rail", config.launcher.logs.clone()));
+
+    // Remove generated helper files after the game is closed
+    // because they can break the game files integrity checks
 
-    command.arg("-c");
-    command.arg(&bash_command);
+    plan.generated_files = Some(GeneratedFiles::new(consts::launcher_dir()?.join("generated_files").join("star_rail")));
 
     // Setup environment
 
-    command.env("WINEARCH", "win64");
-    command.env("WINEPREFIX", &folders.prefix);
+    plan.env("WINEARCH", "win64");
+    plan.env("WINEPREFIX", folders.prefix.to_string_lossy());
 
     // Add environment flags for selected wine
     for (key, value) in features.env.into_iter() {
-        command.env(key, replace_keywords(value, &folders));
+        plan.env(key, keywords.render(value, Quoting::Env)?);
     }
 
     // Add environment flags for selected dxvk
     if let Ok(Some(dxvk )) = config.get_selected_dxvk() {
         if let Ok(Some(features)) = dxvk.features(&config.components.path) {
             for (key, value) in features.env.iter() {
-                command.env(key, replace_keywords(value, &folders));
+                plan.env(key, keywords.render(value, Quoting::Env)?);
             }
         }
     }
@@ -218,71 +314,101 @@ pub fn run() -> anyhow::Result<()> {
         wine_folder.push("files");
     }
 
-    command.envs(config.game.enhancements.hud.get_env_vars(config.game.enhancements.gamescope.enabled));
-    command.envs(config.game.enhancements.fsr.get_env_vars());
+    plan.envs(config.game.enhancements.hud.get_env_vars(config.game.enhancements.gamescope.enabled));
+    plan.envs(config.game.enhancements.fsr.get_env_vars());
 
-    command.envs(config.game.wine.sync.get_env_vars());
-    command.envs(config.game.wine.language.get_env_vars());
-    command.envs(config.game.wine.shared_libraries.get_env_vars(wine_folder));
+    plan.envs(config.game.wine.sync.get_env_vars());
+    plan.envs(config.game.wine.language.get_env_vars());
+    plan.envs(config.game.wine.shared_libraries.get_env_vars(wine_folder));
 
-    command.envs(&config.game.environment);
+    plan.envs(&config.game.environment);
 
-    #[cfg(feature = "sessions")]
-    if let Some(current) = Sessions::get_current()? {
-        Sessions::apply(current, config.get_wine_prefix_path())?;
-    }
+    Ok(plan)
+}
 
-    // Run command
+/// Run the game in background
+/// 
+/// Returns receiver of the game launching events.
+/// Last received event is either `LaunchEvent::Exited` or `LaunchEvent::Failed`
+/// 
+/// If the game is already running using the same wine prefix,
+/// `LaunchEvent::Failed` contains `AlreadyRunning` error
+pub fn launch() -> Receiver<LaunchEvent> {
+    let (sender, receiver) = mpsc::channel();
 
-    let variables = command
-        .get_envs()
-        .map(|(key, value)| format!("{}=\"{}\"", key.to_string_lossy(), value.unwrap_or_default().to_string_lossy()))
-        .fold(String::new(), |acc, env| acc + " " + &env);
+    std::thread::spawn(move || {
+        let mut events = LaunchEvents::new(sender);
 
-    tracing::info!("Running the game with command: {variables} bash -c \"{bash_command}\"");
+        if let Err(err) = launch_with(&mut events) {
+            events.fail(err);
+        }
+    });
 
-    // We use real current dir here because sandboxed one
-    // obviously doesn't exist
-    command.current_dir(game_path)
-        .spawn()?.wait_with_output()?;
+    receiver
+}
 
-    #[cfg(feature = "discord-rpc")]
-    let rpc = if config.launcher.discord_rpc.enabled {
-        Some(DiscordRpc::new(config.launcher.discord_rpc.clone().into()))
-    } else {
-        None
-    };
+#[tracing::instrument(level = "info", skip(events))]
+fn launch_with(events: &mut LaunchEvents) -> anyhow::Result<()> {
+    tracing::info!("Preparing to run the game");
 
-    #[cfg(feature = "discord-rpc")]
-    if let Some(rpc) = &rpc {
-        rpc.update(RpcUpdates::Connect)?;
-    }
+    events.emit(LaunchEvent::Preparing(LaunchStep::Planning))?;
 
-    loop {
-        std::thread::sleep(std::time::Duration::from_secs(3));
+    let config = Config::get_resolved()?;
+    let plan = plan()?;
 
-        let output = Command::new("ps").arg("-A").stdout(Stdio::piped()).output()?;
-        let output = String::from_utf8_lossy(&output.stdout);
+    // Lock the prefix until the game is closed so another launcher
+    // instance couldn't apply sessions to it at the same time
+    // Overlay is mounted first so the lock is placed in its merged view,
+    // and it's unmounted after the lock is released
+    let _overlay = plan.mount_overlay()?;
 
-        if !output.contains("StarRail.exe") {
-            break;
-        }
+    let mut lock = PrefixLock::acquire(&plan.prefix)?;
 
-        #[cfg(feature = "discord-rpc")]
-        if let Some(rpc) = &rpc {
-            rpc.update(RpcUpdates::Update)?;
-        }
+    // Check telemetry servers
+
+    tracing::info!("Checking telemetry");
+
+    events.emit(LaunchEvent::Preparing(LaunchStep::CheckingTelemetry))?;
+
+    if let Ok(Some(server)) = telemetry::is_disabled(config.launcher.edition) {
+        return Err(anyhow::anyhow!("Telemetry server is not disabled: {server}"));
+    }
+
+    #[cfg(feature = "sessions")] {
+        events.subscribe(SessionsSubscriber::<Sessions>::new(&plan.wine_prefix));
+
+        events.emit(LaunchEvent::Preparing(LaunchStep::ApplyingSession))?;
     }
 
+    #[cfg(feature = "stats")]
+    events.subscribe(StatsSubscriber::<Stats>::new("star_rail", Some(format!("{:?}", config.launcher.edition)), config.game.wine.selected.clone()));
+
     #[cfg(feature = "discord-rpc")]
-    if let Some(rpc) = &rpc {
-        rpc.update(RpcUpdates::Disconnect)?;
+    if config.launcher.discord_rpc.enabled {
+        events.subscribe(DiscordRpcSubscriber::new(DiscordRpc::new(config.launcher.discord_rpc.clone().into())));
     }
 
-    #[cfg(feature = "sessions")]
-    if let Some(current) = Sessions::get_current()? {
-        Sessions::update(current, config.get_wine_prefix_path())?;
+    // Run command and wait until all the game processes exit
+
+    events.watch(&plan, &mut lock)
+}
+
+/// Try to run the game
+/// 
+/// This function will freeze thread it was called from while the game is running
+#[tracing::instrument(level = "info", ret)]
+pub fn run() -> anyhow::Result<()> {
+    let mut result = Err(anyhow::anyhow!("Game launching thread stopped unexpectedly"));
+
+    // Failed event can follow the Exited one if post-exit hooks failed
+    for event in launch() {
+        match event {
+            LaunchEvent::Exited { .. } => result = Ok(()),
+            LaunchEvent::Failed(err) => result = Err(err),
+
+            _ => ()
+        }
     }
 
-    Ok(())
+    result
 }
@@ -6,6 +6,9 @@ use anime_game_core::star_rail::prelude::*;
 use crate::config::ConfigExt;
 use crate::star_rail::config::Config;
 
+#[cfg(feature = "diagnostics")]
+use crate::diagnostics::DiagnosticsReport;
+
 #[derive(Debug, Clone)]
 pub enum LauncherState {
     Launch,
@@ -23,8 +26,20 @@ pub enum LauncherState {
     #[cfg(feature = "components")]
     WineNotInstalled,
 
+    /// Wine prefix can be created using `Config::get_prefix_manager`
     PrefixNotExists,
 
+    #[cfg(feature = "components")]
+    /// Wine prefix was initialized or updated by another wine build
+    /// 
+    /// Contains name of that build. Prefix can be updated
+    /// using `PrefixManager::update` from `Config::get_prefix_manager`
+    PrefixUpdateRequired(String),
+
+    #[cfg(feature = "diagnostics")]
+    /// Preflight diagnostics found problems which will most likely break the game
+    DiagnosticsFailed(DiagnosticsReport),
+
     /// Always contains `VersionDiff::Predownload`
     PredownloadAvailable {
         game: VersionDiff,
@@ -189,7 +204,16 @@ impl LauncherState {
 
         match &config.game.wine.selected {
             #[cfg(feature = "components")]
-            Some(selected) if !config.game.wine.builds.join(selected).exists() => return Ok(Self::WineNotInstalled),
+            Some(selected) if !config.game.wine.builds.join(selected).exists() => {
+                // Managed builds are stored outside of the builds folder
+                let installed = config.get_selected_wine()?
+                    .map(|wine| wine.get_runner_dir(&config.game.wine.builds).exists())
+                    .unwrap_or_default();
+
+                if !installed {
+                    return Ok(Self::WineNotInstalled);
+                }
+            }
 
             None => return Ok(Self::WineNotInstalled),
 
@@ -205,15 +229,33 @@ impl LauncherState {
             });
         }
 
-        Self::get(LauncherStateParams {
+        // Check if the prefix should be updated by the selected wine build
+        #[cfg(feature = "components")]
+        if let Some(runner) = config.get_outdated_prefix_runner()? {
+            return Ok(Self::PrefixUpdateRequired(runner));
+        }
+
+        let state = Self::get(LauncherStateParams {
             game_path: config.game.path.for_edition(config.launcher.edition).to_path_buf(),
             game_edition: config.launcher.edition,
 
-            wine_prefix: config.get_wine_prefix_path(),
+            wine_prefix: config.get_wine_prefix_check_path(),
             patch_folder: config.patch.path,
 
             selected_voices: voices,
             status_updater
-        })
+        })?;
+
+        // Run diagnostics only when everything else is ready
+        #[cfg(feature = "diagnostics")]
+        if let Self::Launch = state {
+            let report = Config::get_resolved()?.diagnose();
+
+            if report.has_errors() {
+                return Ok(Self::DiagnosticsFailed(report));
+            }
+        }
+
+        Ok(state)
     }
 }
@@ -0,0 +1,310 @@
+use std::path::{Path, PathBuf};
+
+/// Chars which require an argument of the `Exec` key to be quoted
+const EXEC_RESERVED_CHARS: &[char] = &[
+    ' ', '\t', '\n', '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')', '`'
+];
+
+/// What desktop entry should run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DesktopTarget {
+    /// Run the game directly, skipping the launcher window
+    /// 
+    /// Launcher is run with `--run-game` argument,
+    /// and `--edition <edition>` if the game has editions
+    Game,
+
+    /// Open the launcher
+    Launcher
+}
+
+/// Freedesktop application entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopEntry {
+    /// Entry's file name without extension
+    pub id: String,
+
+    pub name: String,
+    pub comment: Option<String>,
+
+    /// Command to run. First item is the binary
+    pub exec: Vec<String>,
+
+    /// Working directory of the command
+    pub path: Option<PathBuf>,
+
+    /// Icon name from the icons theme or path to the icon
+    pub icon: Option<String>,
+
+    pub categories: Vec<String>
+}
+
+impl DesktopEntry {
+    pub fn new(id: impl ToString, name: impl ToString, exec: Vec<String>) -> Self {
+        Self {
+            id: id.to_string(),
+            name: name.to_string(),
+            comment: None,
+            exec,
+            path: None,
+            icon: None,
+            categories: vec![String::from("Game")]
+        }
+    }
+
+    #[inline]
+    /// Get path to the entry's file in the applications folder
+    pub fn file(&self) -> anyhow::Result<PathBuf> {
+        Ok(applications_dir()?.join(format!("{}.desktop", self.id)))
+    }
+
+    /// Write entry to the applications folder
+    /// 
+    /// Returns path to the written file
+    #[tracing::instrument(level = "debug")]
+    pub fn install(&self) -> anyhow::Result<PathBuf> {
+        let path = self.file()?;
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&path, self.to_string())?;
+
+        Ok(path)
+    }
+
+    /// Remove entry from the applications folder
+    #[tracing::instrument(level = "debug")]
+    pub fn uninstall(&self) -> anyhow::Result<()> {
+        let path = self.file()?;
+
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for DesktopEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "[Desktop Entry]")?;
+        writeln!(f, "Type=Application")?;
+        writeln!(f, "Name={}", escape_string(&self.name))?;
+
+        if let Some(comment) = &self.comment {
+            writeln!(f, "Comment={}", escape_string(comment))?;
+        }
+
+        let exec = self.exec.iter()
+            .map(|arg| quote_exec(arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        writeln!(f, "Exec={exec}")?;
+
+        if let Some(path) = &self.path {
+            writeln!(f, "Path={}", escape_string(&path.to_string_lossy()))?;
+        }
+
+        if let Some(icon) = &self.icon {
+            writeln!(f, "Icon={}", escape_string(icon))?;
+        }
+
+        if !self.categories.is_empty() {
+            writeln!(f, "Categories={};", self.categories.join(";"))?;
+        }
+
+        writeln!(f, "Terminal=false")
+    }
+}
+
+/// Get launcher's command for the desktop entry
+/// 
+/// Launcher folder is passed in `LAUNCHER_FOLDER` variable,
+/// so the entry uses the same launcher data as the current process
+/// 
+/// ```
+/// use anime_launcher_sdk::integrations::desktop::{launcher_command, DesktopTarget};
+/// 
+/// assert_eq!(launcher_command("/usr/bin/launcher", "/data", DesktopTarget::Game, Some("global")), vec![
+///     "env", "LAUNCHER_FOLDER=/data", "/usr/bin/launcher", "--run-game", "--edition", "global"
+/// ]);
+/// 
+/// assert_eq!(launcher_command("/usr/bin/launcher", "/data", DesktopTarget::Launcher, Some("global")), vec![
+///     "env", "LAUNCHER_FOLDER=/data", "/usr/bin/launcher"
+/// ]);
+/// ```
+pub fn launcher_command(launcher: impl AsRef<Path>, launcher_dir: impl AsRef<Path>, target: DesktopTarget, edition: Option<&str>) -> Vec<String> {
+    let mut command = vec![
+        String::from("env"),
+        format!("LAUNCHER_FOLDER={}", launcher_dir.as_ref().to_string_lossy()),
+        launcher.as_ref().to_string_lossy().to_string()
+    ];
+
+    command.extend(launcher_args(target, edition));
+
+    command
+}
+
+/// Get launcher's arguments which make it run the given target
+/// 
+/// Used by the desktop entries and Steam shortcuts
+/// 
+/// ```
+/// use anime_launcher_sdk::integrations::desktop::{launcher_args, DesktopTarget};
+/// 
+/// assert_eq!(launcher_args(DesktopTarget::Game, Some("global")), vec!["--run-game", "--edition", "global"]);
+/// assert_eq!(launcher_args(DesktopTarget::Game, None), vec!["--run-game"]);
+/// 
+/// assert!(launcher_args(DesktopTarget::Launcher, Some("global")).is_empty());
+/// ```
+pub fn launcher_args(target: DesktopTarget, edition: Option<&str>) -> Vec<String> {
+    let mut args = Vec::new();
+
+    if target == DesktopTarget::Game {
+        args.push(String::from("--run-game"));
+
+        if let Some(edition) = edition {
+            args.extend([String::from("--edition"), edition.to_string()]);
+        }
+    }
+
+    args
+}
+
+/// Escape value of the string type
+fn escape_string(value: &str) -> String {
+    value.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r")
+}
+
+/// Quote argument of the `Exec` key
+/// 
+/// ```
+/// use anime_launcher_sdk::integrations::desktop::quote_exec;
+/// 
+/// assert_eq!(quote_exec("/usr/bin/launcher"), "/usr/bin/launcher");
+/// assert_eq!(quote_exec("/path/with space"), "\"/path/with space\"");
+/// assert_eq!(quote_exec("100%"), "100%%");
+/// 
+/// // Escaped by the quoting rules, and then by the string type rules
+/// assert_eq!(quote_exec("$HOME\\games"), "\"\\\\$HOME\\\\\\\\games\"");
+/// ```
+pub fn quote_exec(arg: &str) -> String {
+    let arg = if arg.is_empty() || arg.contains(EXEC_RESERVED_CHARS) {
+        let mut quoted = String::from("\"");
+
+        for c in arg.chars() {
+            if ['"', '`', '$', '\\'].contains(&c) {
+                quoted.push('\\');
+            }
+
+            quoted.push(c);
+        }
+
+        quoted.push('"');
+
+        quoted
+    } else {
+        arg.to_string()
+    };
+
+    escape_string(&arg.replace('%', "%%"))
+}
+
+/// Get user's applications folder (`$XDG_DATA_HOME/applications`)
+pub fn applications_dir() -> anyhow::Result<PathBuf> {
+    dirs::data_dir()
+        .map(|dir| dir.join("applications"))
+        .ok_or_else(|| anyhow::anyhow!("Failed to find user's data folder"))
+}
+
+/// Get user's hicolor icons theme folder (`$XDG_DATA_HOME/icons/hicolor`)
+pub fn icons_dir() -> anyhow::Result<PathBuf> {
+    dirs::data_dir()
+        .map(|dir| dir.join("icons/hicolor"))
+        .ok_or_else(|| anyhow::anyhow!("Failed to find user's data folder"))
+}
+
+/// Get size of the square PNG image
+fn png_size(path: &Path) -> anyhow::Result<u32> {
+    let header = std::fs::read(path)?;
+
+    // Signature (8 bytes), IHDR chunk length and type (8 bytes), width and height (8 bytes)
+    if header.len() < 24 || &header[..8] != b"\x89PNG\r\n\x1a\n" || &header[12..16] != b"IHDR" {
+        anyhow::bail!("Icon is not a PNG image: {:?}", path);
+    }
+
+    let width = u32::from_be_bytes(header[16..20].try_into()?);
+    let height = u32::from_be_bytes(header[20..24].try_into()?);
+
+    if width != height {
+        anyhow::bail!("Icon must be square, got {width}x{height}: {:?}", path);
+    }
+
+    Ok(width)
+}
+
+/// Install PNG or SVG icon into the hicolor icons theme
+/// 
+/// PNG icons are put into the folder of their size.
+/// Returns path to the installed icon
+#[tracing::instrument(level = "debug", ret)]
+pub fn install_icon(name: impl AsRef<str> + std::fmt::Debug, icon: impl AsRef<Path> + std::fmt::Debug) -> anyhow::Result<PathBuf> {
+    let icon = icon.as_ref();
+
+    let path = match icon.extension().and_then(|ext| ext.to_str()) {
+        Some("svg") => icons_dir()?.join("scalable/apps").join(format!("{}.svg", name.as_ref())),
+
+        Some("png") => {
+            let size = png_size(icon)?;
+
+            icons_dir()?.join(format!("{size}x{size}/apps")).join(format!("{}.png", name.as_ref()))
+        }
+
+        _ => anyhow::bail!("Icon must be PNG or SVG image: {:?}", icon)
+    };
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    std::fs::copy(icon, &path)?;
+
+    Ok(path)
+}
+
+/// Remove entries and icons which names start with given prefix
+/// 
+/// Returns paths of the removed files
+#[tracing::instrument(level = "debug", ret)]
+pub fn uninstall_prefixed(prefix: &str) -> anyhow::Result<Vec<PathBuf>> {
+    let mut folders = vec![applications_dir()?];
+
+    // Every icon size has its own folder
+    if let Ok(sizes) = icons_dir()?.read_dir() {
+        folders.extend(sizes.flatten().map(|size| size.path().join("apps")));
+    }
+
+    let mut removed = Vec::new();
+
+    for folder in folders {
+        let Ok(entries) = folder.read_dir() else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(prefix) {
+                std::fs::remove_file(entry.path())?;
+
+                removed.push(entry.path());
+            }
+        }
+    }
+
+    Ok(removed)
+}
@@ -1,3 +1,6 @@
 pub mod steam;
+pub mod vdf;
+pub mod shortcuts;
+pub mod desktop;
 
 //pub mod launcherlauncher;
\ No newline at end of file
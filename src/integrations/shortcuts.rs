@@ -0,0 +1,458 @@
+use std::path::{Path, PathBuf};
+
+use super::vdf::{self, VdfValue};
+use super::desktop::{launcher_args, DesktopTarget};
+
+use crate::shell;
+
+/// Non-Steam game shortcut
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shortcut {
+    /// Shortcut's id. Calculated from the executable and the name
+    pub app_id: u32,
+
+    pub app_name: String,
+
+    /// Path to the executable
+    pub exe: PathBuf,
+
+    /// Working directory of the executable
+    pub start_dir: PathBuf,
+
+    pub icon: Option<PathBuf>,
+
+    /// Arguments passed to the executable
+    pub launch_options: String,
+
+    pub is_hidden: bool,
+    pub allow_desktop_config: bool,
+    pub allow_overlay: bool,
+
+    /// Steam collections the shortcut is added to
+    pub tags: Vec<String>
+}
+
+impl Shortcut {
+    /// Create shortcut for the executable
+    /// 
+    /// Working directory is set to the executable's folder
+    pub fn new(app_name: impl ToString, exe: impl Into<PathBuf>) -> Self {
+        let app_name = app_name.to_string();
+        let exe = exe.into();
+
+        Self {
+            app_id: get_app_id(&exe, &app_name),
+            start_dir: exe.parent().map(Path::to_path_buf).unwrap_or_default(),
+            app_name,
+            exe,
+            icon: None,
+            launch_options: String::new(),
+            is_hidden: false,
+            allow_desktop_config: true,
+            allow_overlay: true,
+            tags: Vec::new()
+        }
+    }
+
+    #[inline]
+    /// Create shortcut which runs the launcher
+    pub fn launcher(app_name: impl ToString, launcher: impl Into<PathBuf>) -> Self {
+        Self::new(app_name, launcher)
+    }
+
+    /// Create shortcut which runs the game directly using the launcher
+    /// 
+    /// Launcher gets the same arguments as in the desktop entries (see `desktop::launcher_args`).
+    /// Shortcut id is calculated from the name, so every game should have its own one
+    pub fn game(app_name: impl ToString, launcher: impl Into<PathBuf>, edition: Option<&str>) -> Self {
+        Self::new(app_name, launcher)
+            .with_launch_options(shell::join(launcher_args(DesktopTarget::Game, edition)))
+    }
+
+    #[inline]
+    pub fn with_start_dir(self, start_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            start_dir: start_dir.into(),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn with_icon(self, icon: impl Into<PathBuf>) -> Self {
+        Self {
+            icon: Some(icon.into()),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn with_launch_options(self, launch_options: impl ToString) -> Self {
+        Self {
+            launch_options: launch_options.to_string(),
+            ..self
+        }
+    }
+
+    #[inline]
+    pub fn with_tags<T: ToString>(self, tags: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            tags: tags.into_iter().map(|tag| tag.to_string()).collect(),
+            ..self
+        }
+    }
+
+    /// Read shortcut from the `shortcuts.vdf` entry
+    /// 
+    /// Returns `None` if the entry doesn't have an executable
+    pub fn from_vdf(value: &VdfValue) -> Option<Self> {
+        let get_str = |key: &str| value.get(key).and_then(VdfValue::as_str).unwrap_or_default();
+        let get_bool = |key: &str, default: bool| value.get(key).and_then(VdfValue::as_int).map(|value| value != 0).unwrap_or(default);
+
+        let exe = value.get("exe").and_then(VdfValue::as_str)?;
+        let app_name = get_str("AppName");
+
+        let exe = PathBuf::from(unquote(exe));
+
+        Some(Self {
+            app_id: value.get("appid")
+                .and_then(VdfValue::as_int)
+                .unwrap_or_else(|| get_app_id(&exe, app_name)),
+
+            app_name: app_name.to_string(),
+            exe,
+            start_dir: PathBuf::from(unquote(get_str("StartDir"))),
+
+            icon: Some(get_str("icon"))
+                .filter(|icon| !icon.is_empty())
+                .map(|icon| PathBuf::from(unquote(icon))),
+
+            launch_options: get_str("LaunchOptions").to_string(),
+            is_hidden: get_bool("IsHidden", false),
+            allow_desktop_config: get_bool("AllowDesktopConfig", true),
+            allow_overlay: get_bool("AllowOverlay", true),
+
+            tags: value.get("tags")
+                .and_then(VdfValue::as_map)
+                .map(|tags| tags.iter()
+                    .filter_map(|(_, tag)| tag.as_str())
+                    .map(String::from)
+                    .collect())
+                .unwrap_or_default()
+        })
+    }
+
+    /// Write shortcut's values to the `shortcuts.vdf` entry
+    /// 
+    /// Values unknown to this struct are kept as is
+    pub fn write_vdf(&self, value: &mut VdfValue) {
+        let bool_value = |value: bool| VdfValue::Int(value as u32);
+
+        let tags = self.tags.iter()
+            .enumerate()
+            .map(|(i, tag)| (i.to_string(), VdfValue::String(tag.clone())))
+            .collect();
+
+        value.set("appid", VdfValue::Int(self.app_id))
+            .set("AppName", VdfValue::String(self.app_name.clone()))
+            .set("Exe", VdfValue::String(quote(&self.exe)))
+            .set("StartDir", VdfValue::String(quote(&self.start_dir)))
+            .set("icon", VdfValue::String(self.icon.as_ref().map(|icon| quote(icon)).unwrap_or_default()))
+            .set("LaunchOptions", VdfValue::String(self.launch_options.clone()))
+            .set("IsHidden", bool_value(self.is_hidden))
+            .set("AllowDesktopConfig", bool_value(self.allow_desktop_config))
+            .set("AllowOverlay", bool_value(self.allow_overlay))
+            .set("tags", VdfValue::Map(tags));
+    }
+}
+
+/// Calculate non-Steam shortcut id the same way as Steam does
+/// 
+/// ```
+/// use anime_launcher_sdk::integrations::shortcuts::get_app_id;
+/// 
+/// let app_id = get_app_id("/usr/bin/anime-game-launcher", "An Anime Game Launcher");
+/// 
+/// // Shortcut ids always have the highest bit set
+/// assert!(app_id >= 0x80000000);
+/// assert_eq!(app_id, get_app_id("/usr/bin/anime-game-launcher", "An Anime Game Launcher"));
+/// ```
+pub fn get_app_id(exe: impl AsRef<Path>, app_name: impl AsRef<str>) -> u32 {
+    let key = format!("{}{}", quote(exe.as_ref()), app_name.as_ref());
+
+    crc32(key.as_bytes()) | 0x80000000
+}
+
+/// CRC-32 (IEEE) checksum
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFF_u32;
+
+    for byte in bytes {
+        crc ^= *byte as u32;
+
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+/// Steam stores paths in shortcuts in double quotes
+fn quote(path: &Path) -> String {
+    format!("\"{}\"", path.to_string_lossy())
+}
+
+fn unquote(value: &str) -> &str {
+    value.strip_prefix('"')
+        .and_then(|value| value.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Steam installation folder
+/// 
+/// Steam overwrites its files on exit, so it should be closed
+/// before adding shortcuts or changing compatibility tools
+/// 
+/// ```
+/// use anime_launcher_sdk::integrations::shortcuts::{SteamRoot, Shortcut};
+/// 
+/// let path = std::env::temp_dir().join("anime-launcher-sdk-steam-root");
+/// 
+/// std::fs::create_dir_all(path.join("userdata/12345/config")).unwrap();
+/// 
+/// let steam = SteamRoot::new(&path);
+/// 
+/// assert_eq!(steam.users().unwrap(), vec![12345]);
+/// 
+/// let shortcut = Shortcut::game("An Anime Game", "/usr/bin/anime-game-launcher", Some("global"))
+///     .with_tags(["Anime"]);
+/// 
+/// steam.add_shortcut(12345, &shortcut).unwrap();
+/// steam.set_compat_tool(shortcut.app_id, Some("proton_experimental")).unwrap();
+/// 
+/// assert_eq!(steam.get_shortcuts(12345).unwrap(), vec![shortcut.clone()]);
+/// assert_eq!(steam.get_compat_tool(shortcut.app_id).unwrap().as_deref(), Some("proton_experimental"));
+/// 
+/// // Existing shortcut is updated
+/// let shortcut = shortcut.with_launch_options("--run-game");
+/// 
+/// steam.add_shortcut(12345, &shortcut).unwrap();
+/// 
+/// assert_eq!(steam.get_shortcuts(12345).unwrap(), vec![shortcut.clone()]);
+/// 
+/// assert!(steam.remove_shortcut(12345, shortcut.app_id).unwrap());
+/// assert!(steam.get_shortcuts(12345).unwrap().is_empty());
+/// 
+/// std::fs::remove_dir_all(path).unwrap();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SteamRoot {
+    pub path: PathBuf
+}
+
+impl SteamRoot {
+    #[inline]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into()
+        }
+    }
+
+    /// Find Steam installation folder
+    pub fn locate() -> Option<Self> {
+        steamlocate::SteamDir::locate().map(|steam| Self::new(steam.path))
+    }
+
+    /// Get ids of the users which have local data
+    pub fn users(&self) -> anyhow::Result<Vec<u64>> {
+        let mut users = Vec::new();
+
+        for entry in self.path.join("userdata").read_dir()?.flatten() {
+            // "0" folder is used when no user is logged in
+            if let Ok(id) = entry.file_name().to_string_lossy().parse::<u64>() {
+                if id != 0 && entry.path().is_dir() {
+                    users.push(id);
+                }
+            }
+        }
+
+        users.sort();
+
+        Ok(users)
+    }
+
+    #[inline]
+    /// Get path to the user's `shortcuts.vdf` file
+    pub fn shortcuts_file(&self, user: u64) -> PathBuf {
+        self.path.join("userdata").join(user.to_string()).join("config/shortcuts.vdf")
+    }
+
+    #[inline]
+    /// Get path to the Steam's `config.vdf` file
+    pub fn config_file(&self) -> PathBuf {
+        self.path.join("config/config.vdf")
+    }
+
+    fn read_shortcuts(&self, user: u64) -> anyhow::Result<VdfValue> {
+        let path = self.shortcuts_file(user);
+
+        if !path.exists() {
+            return Ok(VdfValue::map());
+        }
+
+        vdf::from_binary(&std::fs::read(path)?)
+    }
+
+    #[inline]
+    fn write_shortcuts(&self, user: u64, shortcuts: &VdfValue) -> anyhow::Result<()> {
+        write_file(&self.shortcuts_file(user), vdf::to_binary(shortcuts))
+    }
+
+    /// Get user's non-Steam shortcuts
+    pub fn get_shortcuts(&self, user: u64) -> anyhow::Result<Vec<Shortcut>> {
+        let shortcuts = self.read_shortcuts(user)?;
+
+        Ok(shortcuts.get("shortcuts")
+            .and_then(VdfValue::as_map)
+            .map(|entries| entries.iter()
+                .filter_map(|(_, entry)| Shortcut::from_vdf(entry))
+                .collect())
+            .unwrap_or_default())
+    }
+
+    /// Add non-Steam shortcut to the user's library
+    /// 
+    /// Shortcut with the same id is updated instead
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub fn add_shortcut(&self, user: u64, shortcut: &Shortcut) -> anyhow::Result<()> {
+        let mut root = self.read_shortcuts(user)?;
+        let shortcuts = root.get_or_insert_map("shortcuts");
+
+        let existing = shortcuts.as_map()
+            .unwrap_or_default()
+            .iter()
+            .position(|(_, entry)| Shortcut::from_vdf(entry).map(|entry| entry.app_id) == Some(shortcut.app_id));
+
+        if let (Some(i), VdfValue::Map(entries)) = (existing, &mut *shortcuts) {
+            shortcut.write_vdf(&mut entries[i].1);
+        }
+
+        else {
+            let mut entry = VdfValue::map();
+
+            shortcut.write_vdf(&mut entry);
+
+            let index = shortcuts.as_map().map(|entries| entries.len()).unwrap_or_default();
+
+            shortcuts.set(index.to_string(), entry);
+        }
+
+        self.write_shortcuts(user, &root)
+    }
+
+    /// Remove non-Steam shortcut from the user's library
+    /// 
+    /// Returns `false` if there's no shortcut with given id
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub fn remove_shortcut(&self, user: u64, app_id: u32) -> anyhow::Result<bool> {
+        let mut root = self.read_shortcuts(user)?;
+
+        let Some(VdfValue::Map(entries)) = root.get_mut("shortcuts") else {
+            return Ok(false);
+        };
+
+        let len = entries.len();
+
+        entries.retain(|(_, entry)| Shortcut::from_vdf(entry).map(|entry| entry.app_id) != Some(app_id));
+
+        if entries.len() == len {
+            return Ok(false);
+        }
+
+        // Steam expects entries to be numbered from 0
+        for (i, (key, _)) in entries.iter_mut().enumerate() {
+            *key = i.to_string();
+        }
+
+        self.write_shortcuts(user, &root)?;
+
+        Ok(true)
+    }
+
+    fn read_config(&self) -> anyhow::Result<VdfValue> {
+        let path = self.config_file();
+
+        if !path.exists() {
+            return Ok(VdfValue::map());
+        }
+
+        vdf::from_text(&std::fs::read_to_string(path)?)
+    }
+
+    /// Get name of the compatibility tool (e.g. `proton_experimental`) used by the app
+    pub fn get_compat_tool(&self, app_id: u32) -> anyhow::Result<Option<String>> {
+        let config = self.read_config()?;
+
+        Ok(config.get("InstallConfigStore")
+            .and_then(|value| value.get("Software"))
+            .and_then(|value| value.get("Valve"))
+            .and_then(|value| value.get("Steam"))
+            .and_then(|value| value.get("CompatToolMapping"))
+            .and_then(|value| value.get(app_id.to_string()))
+            .and_then(|value| value.get("name"))
+            .and_then(VdfValue::as_str)
+            .filter(|name| !name.is_empty())
+            .map(String::from))
+    }
+
+    /// Set compatibility tool used by the app
+    /// 
+    /// `None` removes the mapping, so the app will be run natively
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub fn set_compat_tool(&self, app_id: u32, tool: Option<&str>) -> anyhow::Result<()> {
+        let mut config = self.read_config()?;
+
+        let mapping = config.get_or_insert_map("InstallConfigStore")
+            .get_or_insert_map("Software")
+            .get_or_insert_map("Valve")
+            .get_or_insert_map("Steam")
+            .get_or_insert_map("CompatToolMapping");
+
+        match tool {
+            Some(tool) => {
+                let mut entry = VdfValue::map();
+
+                entry.set("name", VdfValue::String(tool.to_string()))
+                    .set("config", VdfValue::String(String::new()))
+                    .set("priority", VdfValue::String(String::from("250")));
+
+                mapping.set(app_id.to_string(), entry);
+            }
+
+            None => {
+                mapping.remove(app_id.to_string());
+            }
+        }
+
+        write_file(&self.config_file(), vdf::to_text(&config))
+    }
+}
+
+/// Write file under a temp name and then rename it,
+/// so Steam never reads a partially written file
+fn write_file(path: &Path, content: impl AsRef<[u8]>) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let temp = path.with_extension("vdf.tmp");
+
+    std::fs::write(&temp, content)?;
+    std::fs::rename(temp, path)?;
+
+    Ok(())
+}
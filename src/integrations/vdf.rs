@@ -0,0 +1,451 @@
+//! Steam's key-value files
+//! 
+//! Binary format is used by `shortcuts.vdf`, text format by `config.vdf`.
+//! Maps keep their keys order, so files can be written back without unnecessary changes
+
+const BINARY_MAP: u8 = 0x00;
+const BINARY_STRING: u8 = 0x01;
+const BINARY_INT: u8 = 0x02;
+const BINARY_FLOAT: u8 = 0x03;
+const BINARY_UINT64: u8 = 0x07;
+const BINARY_MAP_END: u8 = 0x08;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VdfValue {
+    Map(Vec<(String, VdfValue)>),
+    String(String),
+
+    /// Only available in the binary format
+    Int(u32),
+
+    /// Only available in the binary format
+    Float(f32),
+
+    /// Only available in the binary format
+    UInt64(u64)
+}
+
+impl VdfValue {
+    #[inline]
+    pub fn map() -> Self {
+        Self::Map(Vec::new())
+    }
+
+    /// Get map's value by the key (case insensitive, as Steam does)
+    pub fn get(&self, key: impl AsRef<str>) -> Option<&VdfValue> {
+        match self {
+            Self::Map(values) => values.iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key.as_ref()))
+                .map(|(_, value)| value),
+
+            _ => None
+        }
+    }
+
+    /// Get mutable map's value by the key (case insensitive, as Steam does)
+    pub fn get_mut(&mut self, key: impl AsRef<str>) -> Option<&mut VdfValue> {
+        match self {
+            Self::Map(values) => values.iter_mut()
+                .find(|(name, _)| name.eq_ignore_ascii_case(key.as_ref()))
+                .map(|(_, value)| value),
+
+            _ => None
+        }
+    }
+
+    /// Set map's value, keeping position of the existing key
+    /// 
+    /// Does nothing if the value is not a map
+    pub fn set(&mut self, key: impl ToString, value: VdfValue) -> &mut Self {
+        let key = key.to_string();
+
+        if let Some(old) = self.get_mut(&key) {
+            *old = value;
+        }
+
+        else if let Self::Map(values) = self {
+            values.push((key, value));
+        }
+
+        self
+    }
+
+    /// Remove map's value
+    pub fn remove(&mut self, key: impl AsRef<str>) -> Option<VdfValue> {
+        let Self::Map(values) = self else {
+            return None;
+        };
+
+        let pos = values.iter().position(|(name, _)| name.eq_ignore_ascii_case(key.as_ref()))?;
+
+        Some(values.remove(pos).1)
+    }
+
+    /// Get map's value, inserting an empty map if it doesn't exist
+    /// or is not a map
+    pub fn get_or_insert_map(&mut self, key: impl AsRef<str>) -> &mut VdfValue {
+        if !matches!(self.get(&key), Some(Self::Map(_))) {
+            self.set(key.as_ref(), Self::map());
+        }
+
+        self.get_mut(key).expect("Map value must be inserted")
+    }
+
+    #[inline]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None
+        }
+    }
+
+    #[inline]
+    pub fn as_int(&self) -> Option<u32> {
+        match self {
+            Self::Int(value) => Some(*value),
+            _ => None
+        }
+    }
+
+    #[inline]
+    pub fn as_map(&self) -> Option<&[(String, VdfValue)]> {
+        match self {
+            Self::Map(values) => Some(values),
+            _ => None
+        }
+    }
+}
+
+/// Parse binary VDF file content
+/// 
+/// ```
+/// use anime_launcher_sdk::integrations::vdf::{self, VdfValue};
+/// 
+/// let mut value = VdfValue::map();
+/// 
+/// value.set("AppName", VdfValue::String(String::from("An Anime Game")))
+///     .set("appid", VdfValue::Int(3123456789));
+/// 
+/// let bytes = vdf::to_binary(&value);
+/// 
+/// assert_eq!(vdf::from_binary(&bytes).unwrap(), value);
+/// ```
+pub fn from_binary(bytes: &[u8]) -> anyhow::Result<VdfValue> {
+    let mut reader = BinaryReader { bytes, pos: 0 };
+
+    let values = reader.read_map(true)?;
+
+    if reader.pos < bytes.len() {
+        anyhow::bail!("Unexpected data at the end of binary VDF at byte {}", reader.pos);
+    }
+
+    Ok(VdfValue::Map(values))
+}
+
+/// Serialize map into binary VDF format
+/// 
+/// Non-map values are serialized as empty maps
+pub fn to_binary(value: &VdfValue) -> Vec<u8> {
+    let mut bytes = Vec::new();
+
+    if let VdfValue::Map(values) = value {
+        write_binary_map(&mut bytes, values);
+    }
+
+    bytes.push(BINARY_MAP_END);
+
+    bytes
+}
+
+struct BinaryReader<'a> {
+    bytes: &'a [u8],
+    pos: usize
+}
+
+impl BinaryReader<'_> {
+    fn read_byte(&mut self) -> anyhow::Result<u8> {
+        let Some(byte) = self.bytes.get(self.pos) else {
+            anyhow::bail!("Unexpected end of binary VDF");
+        };
+
+        self.pos += 1;
+
+        Ok(*byte)
+    }
+
+    fn read_bytes<const N: usize>(&mut self) -> anyhow::Result<[u8; N]> {
+        let Some(bytes) = self.bytes.get(self.pos..self.pos + N) else {
+            anyhow::bail!("Unexpected end of binary VDF");
+        };
+
+        self.pos += N;
+
+        Ok(bytes.try_into()?)
+    }
+
+    fn read_string(&mut self) -> anyhow::Result<String> {
+        let Some(len) = self.bytes[self.pos..].iter().position(|byte| *byte == 0) else {
+            anyhow::bail!("Unterminated string in binary VDF at byte {}", self.pos);
+        };
+
+        let string = String::from_utf8_lossy(&self.bytes[self.pos..self.pos + len]).to_string();
+
+        self.pos += len + 1;
+
+        Ok(string)
+    }
+
+    fn read_map(&mut self, root: bool) -> anyhow::Result<Vec<(String, VdfValue)>> {
+        let mut values = Vec::new();
+
+        loop {
+            // Some tools don't write the root map end byte
+            if root && self.pos >= self.bytes.len() {
+                return Ok(values);
+            }
+
+            let kind = self.read_byte()?;
+
+            if kind == BINARY_MAP_END {
+                return Ok(values);
+            }
+
+            let key = self.read_string()?;
+
+            let value = match kind {
+                BINARY_MAP => VdfValue::Map(self.read_map(false)?),
+                BINARY_STRING => VdfValue::String(self.read_string()?),
+                BINARY_INT => VdfValue::Int(u32::from_le_bytes(self.read_bytes()?)),
+                BINARY_FLOAT => VdfValue::Float(f32::from_le_bytes(self.read_bytes()?)),
+                BINARY_UINT64 => VdfValue::UInt64(u64::from_le_bytes(self.read_bytes()?)),
+
+                _ => anyhow::bail!("Unknown binary VDF value type {kind:#04x} at byte {}", self.pos)
+            };
+
+            values.push((key, value));
+        }
+    }
+}
+
+fn write_binary_map(bytes: &mut Vec<u8>, values: &[(String, VdfValue)]) {
+    for (key, value) in values {
+        let kind = match value {
+            VdfValue::Map(_) => BINARY_MAP,
+            VdfValue::String(_) => BINARY_STRING,
+            VdfValue::Int(_) => BINARY_INT,
+            VdfValue::Float(_) => BINARY_FLOAT,
+            VdfValue::UInt64(_) => BINARY_UINT64
+        };
+
+        bytes.push(kind);
+        bytes.extend(key.as_bytes());
+        bytes.push(0);
+
+        match value {
+            VdfValue::Map(values) => {
+                write_binary_map(bytes, values);
+
+                bytes.push(BINARY_MAP_END);
+            }
+
+            VdfValue::String(value) => {
+                bytes.extend(value.as_bytes());
+                bytes.push(0);
+            }
+
+            VdfValue::Int(value) => bytes.extend(value.to_le_bytes()),
+            VdfValue::Float(value) => bytes.extend(value.to_le_bytes()),
+            VdfValue::UInt64(value) => bytes.extend(value.to_le_bytes())
+        }
+    }
+}
+
+/// Parse text VDF file content
+/// 
+/// ```
+/// use anime_launcher_sdk::integrations::vdf::{self, VdfValue};
+/// 
+/// let value = vdf::from_text(r#"
+///     "InstallConfigStore"
+///     {
+///         // Comment
+///         "Software"
+///         {
+///             "Path"    "C:\\Games"
+///         }
+///     }
+/// "#).unwrap();
+/// 
+/// let path = value.get("InstallConfigStore")
+///     .and_then(|value| value.get("software"))
+///     .and_then(|value| value.get("path"))
+///     .and_then(VdfValue::as_str);
+/// 
+/// assert_eq!(path, Some("C:\\Games"));
+/// assert_eq!(vdf::from_text(&vdf::to_text(&value)).unwrap(), value);
+/// ```
+pub fn from_text(text: &str) -> anyhow::Result<VdfValue> {
+    let mut tokens = TextTokens {
+        chars: text.chars().peekable(),
+        line: 1
+    };
+
+    let values = read_text_map(&mut tokens, true)?;
+
+    Ok(VdfValue::Map(values))
+}
+
+/// Serialize map into text VDF format
+/// 
+/// Text format has only maps and strings, so numbers are written as strings
+pub fn to_text(value: &VdfValue) -> String {
+    let mut text = String::new();
+
+    if let VdfValue::Map(values) = value {
+        write_text_map(&mut text, values, 0);
+    }
+
+    text
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum TextToken {
+    String(String),
+    MapStart,
+    MapEnd
+}
+
+struct TextTokens<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize
+}
+
+impl TextTokens<'_> {
+    fn next_token(&mut self) -> anyhow::Result<Option<TextToken>> {
+        while let Some(c) = self.chars.next() {
+            match c {
+                '\n' => self.line += 1,
+
+                c if c.is_whitespace() => (),
+
+                '{' => return Ok(Some(TextToken::MapStart)),
+                '}' => return Ok(Some(TextToken::MapEnd)),
+
+                // Comments
+                '/' if self.chars.peek() == Some(&'/') => {
+                    for c in self.chars.by_ref() {
+                        if c == '\n' {
+                            self.line += 1;
+
+                            break;
+                        }
+                    }
+                }
+
+                '"' => {
+                    let mut string = String::new();
+
+                    loop {
+                        match self.chars.next() {
+                            Some('"') => break,
+
+                            Some('\\') => match self.chars.next() {
+                                Some('n') => string.push('\n'),
+                                Some('t') => string.push('\t'),
+                                Some(c) => string.push(c),
+                                None => anyhow::bail!("Unterminated string in VDF at line {}", self.line)
+                            }
+
+                            Some(c) => {
+                                if c == '\n' {
+                                    self.line += 1;
+                                }
+
+                                string.push(c);
+                            }
+
+                            None => anyhow::bail!("Unterminated string in VDF at line {}", self.line)
+                        }
+                    }
+
+                    return Ok(Some(TextToken::String(string)));
+                }
+
+                // Unquoted string
+                c => {
+                    let mut string = String::from(c);
+
+                    while let Some(c) = self.chars.peek() {
+                        if c.is_whitespace() || ['{', '}', '"'].contains(c) {
+                            break;
+                        }
+
+                        string.push(*c);
+
+                        self.chars.next();
+                    }
+
+                    return Ok(Some(TextToken::String(string)));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+fn read_text_map(tokens: &mut TextTokens, root: bool) -> anyhow::Result<Vec<(String, VdfValue)>> {
+    let mut values = Vec::new();
+
+    loop {
+        let key = match tokens.next_token()? {
+            Some(TextToken::String(key)) => key,
+            Some(TextToken::MapEnd) if !root => return Ok(values),
+            None if root => return Ok(values),
+
+            Some(token) => anyhow::bail!("Unexpected {token:?} in VDF at line {}", tokens.line),
+            None => anyhow::bail!("Unexpected end of VDF")
+        };
+
+        let value = match tokens.next_token()? {
+            Some(TextToken::String(value)) => VdfValue::String(value),
+            Some(TextToken::MapStart) => VdfValue::Map(read_text_map(tokens, false)?),
+
+            Some(token) => anyhow::bail!("Unexpected {token:?} in VDF at line {}", tokens.line),
+            None => anyhow::bail!("Unexpected end of VDF")
+        };
+
+        values.push((key, value));
+    }
+}
+
+fn escape_text(string: &str) -> String {
+    string.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn write_text_map(text: &mut String, values: &[(String, VdfValue)], depth: usize) {
+    let indent = "\t".repeat(depth);
+
+    for (key, value) in values {
+        match value {
+            VdfValue::Map(values) => {
+                text.push_str(&format!("{indent}\"{}\"\n{indent}{{\n", escape_text(key)));
+
+                write_text_map(text, values, depth + 1);
+
+                text.push_str(&format!("{indent}}}\n"));
+            }
+
+            VdfValue::String(value) => write_text_value(text, &indent, key, value),
+
+            VdfValue::Int(value) => write_text_value(text, &indent, key, &value.to_string()),
+            VdfValue::Float(value) => write_text_value(text, &indent, key, &value.to_string()),
+            VdfValue::UInt64(value) => write_text_value(text, &indent, key, &value.to_string())
+        }
+    }
+}
+
+#[inline]
+fn write_text_value(text: &mut String, indent: &str, key: &str, value: &str) {
+    text.push_str(&format!("{indent}\"{}\"\t\t\"{}\"\n", escape_text(key), escape_text(value)));
+}
@@ -0,0 +1,126 @@
+use crate::shell;
+use crate::template::{Keywords, Quoting, TemplateError};
+
+/// Structured game launching command
+/// 
+/// `[wrappers] <runner> <windows command> <launch args>`
+/// 
+/// Wrappers are programs like `gamemoderun`, `gamescope` or `bwrap`
+/// which run the rest of the command. The outermost wrapper goes first.
+/// 
+/// Command is spawned directly, without any shell, so paths with
+/// special characters don't need to be escaped
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LaunchCommand {
+    /// Programs wrapping the runner, each with its own arguments
+    pub wrappers: Vec<Vec<String>>,
+
+    /// Wine or proton runner command
+    pub runner: Vec<String>,
+
+    /// Windows command which will be run by the runner
+    pub windows_command: Vec<String>,
+
+    /// Arguments passed to the game
+    pub launch_args: Vec<String>
+}
+
+impl LaunchCommand {
+    /// Wrap current command with a new outermost wrapper
+    pub fn wrap<T: ToString>(&mut self, wrapper: impl IntoIterator<Item = T>) -> &mut Self {
+        self.wrappers.insert(0, wrapper.into_iter().map(|arg| arg.to_string()).collect());
+
+        self
+    }
+
+    /// Add arguments to the windows command
+    pub fn windows_args<T: ToString>(&mut self, args: impl IntoIterator<Item = T>) -> &mut Self {
+        self.windows_command.extend(args.into_iter().map(|arg| arg.to_string()));
+
+        self
+    }
+
+    /// Add arguments passed to the game
+    pub fn launch_args<T: ToString>(&mut self, args: impl IntoIterator<Item = T>) -> &mut Self {
+        self.launch_args.extend(args.into_iter().map(|arg| arg.to_string()));
+
+        self
+    }
+
+    /// Apply function to every argument of the command
+    pub fn map_args(&mut self, mut f: impl FnMut(&str) -> String) -> &mut Self {
+        for wrapper in &mut self.wrappers {
+            for arg in wrapper.iter_mut() {
+                *arg = f(arg);
+            }
+        }
+
+        for arg in self.runner.iter_mut().chain(&mut self.windows_command).chain(&mut self.launch_args) {
+            *arg = f(arg);
+        }
+
+        self
+    }
+
+    /// Get wrappers and runner arguments
+    /// 
+    /// This is a `%bash_command%` part of the launch command
+    pub fn bash_command(&self) -> Vec<String> {
+        self.wrappers.iter()
+            .flatten()
+            .chain(&self.runner)
+            .cloned()
+            .collect()
+    }
+
+    /// Get all the command arguments
+    /// 
+    /// This is a `%command%` part of the launch command
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = self.bash_command();
+
+        args.extend(self.windows_command.iter().cloned());
+        args.extend(self.launch_args.iter().cloned());
+
+        args
+    }
+
+    /// Get content of the `compact_launch.bat` file which runs
+    /// windows command with launch arguments
+    pub fn compact_launch_script(&self) -> String {
+        let mut args = self.windows_command.iter()
+            .chain(&self.launch_args)
+            .map(shell::quote_batch)
+            .collect::<Vec<_>>();
+
+        // `start` uses first quoted argument as the window title
+        if args.first().map(|arg| arg.starts_with('"')).unwrap_or_default() {
+            args.insert(0, String::from("\"\""));
+        }
+
+        format!("start {}\nexit", args.join(" "))
+    }
+
+    /// Apply user-given launch command template
+    /// 
+    /// Command keywords (expanded into properly quoted arguments):
+    /// - `%command%` - whole launch command
+    /// - `%bash_command%` - wrappers and runner
+    /// - `%windows_command%` - windows command
+    /// - `%launch_args%` - game launch arguments
+    /// 
+    /// Other given keywords are quoted as shell arguments.
+    /// Returned command is run by `bash`
+    pub fn with_template(&self, template: impl AsRef<str>, mut keywords: Keywords) -> Result<Vec<String>, TemplateError> {
+        keywords.set_raw("command", shell::join(self.to_args()))
+            .set_raw("bash_command", shell::join(self.bash_command()))
+            .set_raw("windows_command", shell::join(&self.windows_command))
+            .set_raw("launch_args", shell::join(&self.launch_args));
+
+        Ok(vec![
+            String::from("bash"),
+            String::from("-c"),
+            keywords.render(template, Quoting::Shell)?
+        ])
+    }
+}
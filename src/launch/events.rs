@@ -0,0 +1,208 @@
+use std::sync::mpsc::Sender;
+use std::time::{Duration, Instant};
+
+use super::{LaunchPlan, PrefixLock};
+use super::wineserver::WineserverReport;
+use super::subscribers::HooksSubscriber;
+
+/// Interval between `LaunchEvent::Running` events
+pub const RUNNING_INTERVAL: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LaunchStep {
+    /// Reading config and building the launching plan
+    Planning,
+
+    /// Checking that telemetry servers are disabled
+    CheckingTelemetry,
+
+    /// Downloading FPS unlocker if needed
+    PreparingFpsUnlocker,
+
+    /// Applying selected game session
+    ApplyingSession,
+
+    /// Running pre-launch hooks
+    RunningHooks,
+
+    /// Writing helper files, mapping prefix drives and spawning the command
+    Spawning
+}
+
+#[derive(Debug)]
+pub enum LaunchEvent {
+    Preparing(LaunchStep),
+
+    /// Launching command was spawned
+    /// 
+    /// `pid` is also the ID of the tracked process group
+    Spawned {
+        pid: u32
+    },
+
+    /// Game is still running
+    /// 
+    /// Sent right after spawning and then every `RUNNING_INTERVAL`
+    Running,
+
+    /// Leftover wine processes were stopped after the game exit
+    /// 
+    /// Sent before `Exited` if the plan has wineserver cleanup
+    WineserverStopped(WineserverReport),
+
+    /// All the game processes exited. This is the last event,
+    /// unless it is followed by `Failed` if post-exit hooks failed
+    /// 
+    /// `code` is the exit code of the spawned command
+    Exited {
+        code: Option<i32>,
+        duration: Duration
+    },
+
+    /// Game launching failed. This is the last event
+    Failed(anyhow::Error)
+}
+
+/// Internal handler of the launching events
+/// 
+/// Subscribers are called before the event is sent to the frontend
+pub trait LaunchSubscriber: Send {
+    fn handle(&mut self, event: &LaunchEvent) -> anyhow::Result<()>;
+}
+
+/// Sender of the launching events
+pub struct LaunchEvents {
+    sender: Sender<LaunchEvent>,
+    subscribers: Vec<Box<dyn LaunchSubscriber>>
+}
+
+impl LaunchEvents {
+    #[inline]
+    pub fn new(sender: Sender<LaunchEvent>) -> Self {
+        Self {
+            sender,
+            subscribers: Vec::new()
+        }
+    }
+
+    #[inline]
+    /// Add events subscriber
+    pub fn subscribe(&mut self, subscriber: impl LaunchSubscriber + 'static) -> &mut Self {
+        self.subscribers.push(Box::new(subscriber));
+
+        self
+    }
+
+    /// Pass event to all the subscribers and send it to the frontend
+    /// 
+    /// Event is sent even if some subscriber failed. First subscriber's error is returned
+    pub fn emit(&mut self, event: LaunchEvent) -> anyhow::Result<()> {
+        let mut result = Ok(());
+
+        for subscriber in &mut self.subscribers {
+            if let Err(err) = subscriber.handle(&event) {
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+
+        // Frontend could stop listening for events, so we ignore this error
+        let _ = self.sender.send(event);
+
+        result
+    }
+
+    /// Send `LaunchEvent::Failed` event
+    pub fn fail(&mut self, error: anyhow::Error) {
+        tracing::error!("Failed to run the game: {error}");
+
+        if let Err(err) = self.emit(LaunchEvent::Failed(error)) {
+            tracing::warn!("Failed to handle game launching failure: {err}");
+        }
+    }
+
+    /// Pass event to all the subscribers and send it to the frontend
+    /// 
+    /// Subscribers' errors are logged instead of being returned,
+    /// so they can't interrupt watching the running game
+    fn emit_logged(&mut self, event: LaunchEvent) {
+        if let Err(err) = self.emit(event) {
+            tracing::warn!("Failed to handle game launching event: {err}");
+        }
+    }
+
+    /// Execute launching plan and send its events until all the game processes exit
+    /// 
+    /// Plan's hooks are subscribed after all the other subscribers.
+    /// Only errors of the events sent before spawning the game stop it from running.
+    /// Once the game is spawned, helper files are always cleaned up and `Exited`
+    /// is always sent after the game exits, even if some subscriber failed
+    /// 
+    /// Prefix lock is handed over to the spawned game, so it's held
+    /// until the game exits even if the launcher crashed
+    pub fn watch(&mut self, plan: &LaunchPlan, lock: &mut PrefixLock) -> anyhow::Result<()> {
+        self.subscribe(HooksSubscriber::new(plan.hooks.clone(), plan.keywords.clone()));
+
+        self.emit(LaunchEvent::Preparing(LaunchStep::RunningHooks))?;
+        self.emit(LaunchEvent::Preparing(LaunchStep::Spawning))?;
+
+        let started = Instant::now();
+        let process = plan.execute()?;
+
+        let cleanup = CleanupGuard(plan);
+
+        if let Err(err) = lock.hand_over(process.pgid()) {
+            tracing::warn!("Failed to pass prefix lock to the game: {err}");
+        }
+
+        self.emit_logged(LaunchEvent::Spawned {
+            pid: process.pid()
+        });
+
+        while process.is_running() {
+            self.emit_logged(LaunchEvent::Running);
+
+            std::thread::sleep(RUNNING_INTERVAL);
+        }
+
+        let code = match process.wait() {
+            Ok(status) => status.code(),
+            Err(err) => {
+                tracing::warn!("Failed to get game exit status: {err}");
+
+                None
+            }
+        };
+
+        let duration = started.elapsed();
+
+        if let Some(wineserver) = &plan.wineserver {
+            match wineserver.run() {
+                Ok(report) => self.emit_logged(LaunchEvent::WineserverStopped(report)),
+                Err(err) => tracing::warn!("Failed to stop wineserver: {err}")
+            }
+        }
+
+        drop(cleanup);
+
+        // Error of this event means that post-exit hooks failed,
+        // so it's returned and sent as `Failed` after `Exited`
+        self.emit(LaunchEvent::Exited {
+            code,
+            duration
+        })
+    }
+}
+
+/// Clean up plan's helper files when the game watching is finished,
+/// even if it was interrupted by a panic
+struct CleanupGuard<'a>(&'a LaunchPlan);
+
+impl Drop for CleanupGuard<'_> {
+    fn drop(&mut self) {
+        if let Err(err) = self.0.cleanup() {
+            tracing::warn!("Failed to clean up helper files: {err}");
+        }
+    }
+}
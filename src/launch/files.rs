@@ -0,0 +1,149 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Serialize, Deserialize};
+
+/// Helper file written by the launcher
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GeneratedFile {
+    pub path: PathBuf,
+
+    /// Copy of the file which was overwritten by the generated one
+    pub backup: Option<PathBuf>
+}
+
+/// Storage of the generated helper files list
+/// 
+/// Every file is added to the manifest before being written,
+/// so files left after a crash can be cleaned up on the next launch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedFiles {
+    pub folder: PathBuf
+}
+
+impl GeneratedFiles {
+    #[inline]
+    pub fn new(folder: impl Into<PathBuf>) -> Self {
+        Self {
+            folder: folder.into()
+        }
+    }
+
+    #[inline]
+    /// Get path to the manifest file
+    pub fn manifest(&self) -> PathBuf {
+        self.folder.join("manifest.json")
+    }
+
+    #[inline]
+    /// Get path to the folder with overwritten files
+    pub fn backups(&self) -> PathBuf {
+        self.folder.join("backups")
+    }
+
+    /// Get list of the tracked files
+    /// 
+    /// Returns empty list if the manifest doesn't exist
+    pub fn list(&self) -> anyhow::Result<Vec<GeneratedFile>> {
+        let manifest = self.manifest();
+
+        if !manifest.exists() {
+            return Ok(Vec::new());
+        }
+
+        Ok(serde_json::from_slice(&std::fs::read(manifest)?)?)
+    }
+
+    fn save(&self, files: &[GeneratedFile]) -> anyhow::Result<()> {
+        if files.is_empty() {
+            let manifest = self.manifest();
+
+            if manifest.exists() {
+                std::fs::remove_file(manifest)?;
+            }
+
+            return Ok(());
+        }
+
+        std::fs::create_dir_all(&self.folder)?;
+        std::fs::write(self.manifest(), serde_json::to_string_pretty(files)?)?;
+
+        Ok(())
+    }
+
+    /// Write file, saving a copy of the existing one
+    #[tracing::instrument(level = "debug", skip(content))]
+    pub fn write(&self, path: impl AsRef<Path> + std::fmt::Debug, content: impl AsRef<[u8]>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+
+        let mut files = self.list()?;
+
+        if !files.iter().any(|file| file.path == path) {
+            let backup = if path.exists() {
+                let backups = self.backups();
+
+                std::fs::create_dir_all(&backups)?;
+
+                // Backups of the files which failed to be restored can still be there
+                let backup = (0..)
+                    .map(|i| backups.join(i.to_string()))
+                    .find(|backup| !backup.exists())
+                    .expect("Infinite iterator must return a value");
+
+                std::fs::copy(path, &backup)?;
+
+                Some(backup)
+            } else {
+                None
+            };
+
+            files.push(GeneratedFile {
+                path: path.to_path_buf(),
+                backup
+            });
+
+            self.save(&files)?;
+        }
+
+        std::fs::write(path, content)?;
+
+        Ok(())
+    }
+
+    /// Restore overwritten files and remove the other generated ones
+    /// 
+    /// Files which couldn't be cleaned up are kept in the manifest
+    #[tracing::instrument(level = "debug")]
+    pub fn cleanup(&self) -> anyhow::Result<()> {
+        let files = self.list()?;
+
+        if files.is_empty() {
+            return Ok(());
+        }
+
+        let mut failed = Vec::new();
+
+        for file in files {
+            let result = match &file.backup {
+                Some(backup) => std::fs::copy(backup, &file.path)
+                    .and_then(|_| std::fs::remove_file(backup)),
+
+                None if file.path.exists() => std::fs::remove_file(&file.path),
+                None => Ok(())
+            };
+
+            if let Err(err) = result {
+                tracing::warn!("Failed to clean up generated file {:?}: {err}", file.path);
+
+                failed.push(file);
+            }
+        }
+
+        self.save(&failed)?;
+
+        if !failed.is_empty() {
+            anyhow::bail!("Failed to clean up {} generated files", failed.len());
+        }
+
+        Ok(())
+    }
+}
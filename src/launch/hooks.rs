@@ -0,0 +1,69 @@
+use std::process::Command;
+use std::os::unix::process::CommandExt;
+use std::time::{Duration, Instant};
+
+use crate::config::schema_blanks::prelude::{Hook, HookFailure};
+use crate::template::{Keywords, Quoting};
+
+use super::GameProcess;
+
+/// Interval between hook's exit status checks
+const HOOK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Run the hook's command, replacing given keywords in it
+/// 
+/// Keywords are quoted as shell arguments. Unknown keywords are errors.
+/// Command's background processes are not waited, but they will be
+/// killed together with the command if it timed out
+#[tracing::instrument(level = "info", skip(keywords))]
+pub fn run(hook: &Hook, keywords: &Keywords) -> anyhow::Result<()> {
+    let command = keywords.render(&hook.command, Quoting::Shell)?;
+
+    tracing::info!("Running hook: {command}");
+
+    let child = Command::new("bash")
+        .arg("-c")
+        .arg(&command)
+        .process_group(0)
+        .spawn()?;
+
+    let process = GameProcess::new(child);
+    let started = Instant::now();
+
+    let status = loop {
+        if let Some(status) = process.exit_status() {
+            break status;
+        }
+
+        if hook.timeout > 0 && started.elapsed() >= Duration::from_secs(hook.timeout) {
+            process.terminate(true)?;
+
+            anyhow::bail!("Hook timed out after {} seconds: {command}", hook.timeout);
+        }
+
+        std::thread::sleep(HOOK_POLL_INTERVAL);
+    };
+
+    if !status.success() {
+        anyhow::bail!("Hook failed with {status}: {command}");
+    }
+
+    Ok(())
+}
+
+/// Run hooks one by one
+/// 
+/// Returns error of the first failed hook with `HookFailure::Fail` policy.
+/// Other failures are printed as warnings
+pub fn run_all(hooks: &[Hook], keywords: &Keywords) -> anyhow::Result<()> {
+    for hook in hooks {
+        if let Err(err) = run(hook, keywords) {
+            match hook.on_failure {
+                HookFailure::Fail => return Err(err),
+                HookFailure::Warn => tracing::warn!("{err}")
+            }
+        }
+    }
+
+    Ok(())
+}
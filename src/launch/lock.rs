@@ -0,0 +1,204 @@
+use std::path::{Path, PathBuf};
+use std::fs::File;
+
+use super::process::{read_stat, get_group_pids};
+
+/// Name of the lock file in the wine prefix
+pub const LOCK_FILE_NAME: &str = ".launcher.lock";
+
+/// Name of the file locked while the lock file is changed
+const GUARD_FILE_NAME: &str = ".launcher.lock.guard";
+
+/// Game is already running using the same wine prefix
+/// 
+/// Returned by `PrefixLock::acquire` inside `anyhow::Error`,
+/// so it can be found using `error.downcast_ref::<AlreadyRunning>()`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlreadyRunning {
+    /// Process (or process group) which holds the lock
+    pub pid: u32,
+
+    pub lock: PathBuf
+}
+
+impl std::fmt::Display for AlreadyRunning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Game is already running (pid {})", self.pid)
+    }
+}
+
+impl std::error::Error for AlreadyRunning {}
+
+/// Process which holds the lock, stored in the lock file as `<pid> <start time>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LockHolder {
+    pid: u32,
+
+    /// Start time from `/proc/<pid>/stat`, so the reused PID is not taken for the holder
+    start_time: u64
+}
+
+impl LockHolder {
+    fn new(pid: u32) -> anyhow::Result<Self> {
+        let Some(stat) = read_stat(pid) else {
+            anyhow::bail!("Failed to read process info: {pid}");
+        };
+
+        Ok(Self {
+            pid,
+            start_time: stat.start_time
+        })
+    }
+
+    fn read(path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(path).ok()?;
+        let mut fields = content.split_whitespace();
+
+        Some(Self {
+            pid: fields.next()?.parse().ok()?,
+
+            // Locks without start time can't be checked for the PID reuse
+            start_time: fields.next()?.parse().ok()?
+        })
+    }
+
+    /// Check if the holder process, or any process of its group, is still running
+    /// 
+    /// Game process group outlives the spawned command, and PID of the group
+    /// leader can't be reused while the group has any processes
+    fn is_running(&self) -> bool {
+        match read_stat(self.pid) {
+            Some(stat) if stat.start_time != self.start_time => false,
+            Some(stat) if stat.is_alive() => true,
+            _ => !get_group_pids(self.pid).is_empty()
+        }
+    }
+}
+
+impl std::fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.pid, self.start_time)
+    }
+}
+
+/// Advisory lock of the wine prefix
+/// 
+/// Lock file contains PID and start time of the process which holds it.
+/// It's the launcher process until the game is spawned, and the game's
+/// process group after that (see `PrefixLock::hand_over`), so the prefix
+/// stays locked if the launcher crashed while the game is running.
+/// 
+/// Lock is released when this struct is dropped. Locks of the processes
+/// which don't exist anymore are considered stale and replaced
+#[derive(Debug)]
+pub struct PrefixLock {
+    path: PathBuf,
+    holder: LockHolder
+}
+
+impl PrefixLock {
+    #[inline]
+    /// Get path to the prefix's lock file
+    pub fn lock_file(prefix: impl AsRef<Path>) -> PathBuf {
+        prefix.as_ref().join(LOCK_FILE_NAME)
+    }
+
+    /// Get PID of the running process which holds the prefix's lock
+    pub fn holder(prefix: impl AsRef<Path>) -> Option<u32> {
+        LockHolder::read(&Self::lock_file(prefix))
+            .filter(LockHolder::is_running)
+            .map(|holder| holder.pid)
+    }
+
+    /// Lock the wine prefix
+    /// 
+    /// Returns `AlreadyRunning` error if the prefix is locked by another running process
+    #[tracing::instrument(level = "debug")]
+    pub fn acquire(prefix: impl AsRef<Path> + std::fmt::Debug) -> anyhow::Result<Self> {
+        let path = Self::lock_file(&prefix);
+        let holder = LockHolder::new(std::process::id())?;
+
+        let _guard = Self::lock_guard(&path)?;
+
+        if let Some(current) = LockHolder::read(&path) {
+            if current.is_running() {
+                return Err(AlreadyRunning {
+                    pid: current.pid,
+                    lock: path
+                }.into());
+            }
+
+            tracing::warn!("Replacing stale prefix lock: {path:?}");
+        }
+
+        Self::write(&path, holder)?;
+
+        Ok(Self { path, holder })
+    }
+
+    /// Pass the lock to the spawned game's process group
+    /// 
+    /// Lock is still released when this struct is dropped, but if the launcher
+    /// crashed, then it's held until all the game processes exit
+    pub fn hand_over(&mut self, pgid: u32) -> anyhow::Result<()> {
+        let holder = LockHolder::new(pgid)?;
+
+        let _guard = Self::lock_guard(&self.path)?;
+
+        if LockHolder::read(&self.path) != Some(self.holder) {
+            anyhow::bail!("Prefix lock was taken by another process: {:?}", self.path);
+        }
+
+        Self::write(&self.path, holder)?;
+
+        self.holder = holder;
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Lock the guard file so only one process reads and changes the lock file at a time
+    /// 
+    /// Guard is unlocked by the system when the returned file is closed,
+    /// even if the process was killed
+    fn lock_guard(path: &Path) -> anyhow::Result<File> {
+        let guard = File::create(path.with_file_name(GUARD_FILE_NAME))?;
+
+        guard.lock()?;
+
+        Ok(guard)
+    }
+
+    /// Atomically replace the lock file, so other processes never see it empty
+    fn write(path: &Path, holder: LockHolder) -> anyhow::Result<()> {
+        let temp = path.with_extension(format!("lock.{}", std::process::id()));
+
+        std::fs::write(&temp, holder.to_string())?;
+        std::fs::rename(temp, path)?;
+
+        Ok(())
+    }
+
+    fn release(&self) -> anyhow::Result<()> {
+        let _guard = Self::lock_guard(&self.path)?;
+
+        // Don't remove the lock if it was replaced by another process
+        if LockHolder::read(&self.path) == Some(self.holder) {
+            std::fs::remove_file(&self.path)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for PrefixLock {
+    fn drop(&mut self) {
+        if let Err(err) = self.release() {
+            tracing::warn!("Failed to remove prefix lock {:?}: {err}", self.path);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::path::PathBuf;
+use std::fs::File;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::schema_blanks::prelude::Logs;
+
+/// Storage of the game launching logs
+/// 
+/// Every launch is written to its own `<folder>/<timestamp>.log` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchLogs {
+    pub folder: PathBuf,
+    pub params: Logs
+}
+
+impl LaunchLogs {
+    /// Get logs storage for the given game
+    /// 
+    /// `<launcher_dir>/logs/<game>`
+    #[inline]
+    pub fn new(launcher_dir: impl Into<PathBuf>, game: impl AsRef<str>, params: Logs) -> Self {
+        Self {
+            folder: launcher_dir.into().join("logs").join(game.as_ref()),
+            params
+        }
+    }
+
+    /// List stored log files, from oldest to newest
+    pub fn list(&self) -> anyhow::Result<Vec<PathBuf>> {
+        if !self.folder.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut logs = self.folder.read_dir()?
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map(|ext| ext == "log").unwrap_or_default())
+            .collect::<Vec<_>>();
+
+        // Names are millisecond timestamps with the same length,
+        // so they can be sorted as strings
+        logs.sort();
+
+        Ok(logs)
+    }
+
+    #[inline]
+    /// Get the latest log file
+    pub fn latest(&self) -> anyhow::Result<Option<PathBuf>> {
+        Ok(self.list()?.pop())
+    }
+
+    /// Create new log file and remove old ones above the retention limit
+    pub fn create(&self) -> anyhow::Result<(PathBuf, File)> {
+        std::fs::create_dir_all(&self.folder)?;
+
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+
+        let path = self.folder.join(format!("{timestamp}.log"));
+        let file = File::create(&path)?;
+
+        if let Err(err) = self.rotate() {
+            tracing::warn!("Failed to remove old game logs: {err}");
+        }
+
+        Ok((path, file))
+    }
+
+    /// Remove the oldest log files so only `params.retention` of them remain
+    pub fn rotate(&self) -> anyhow::Result<()> {
+        let retention = self.params.retention as usize;
+
+        if retention == 0 {
+            return Ok(());
+        }
+
+        let logs = self.list()?;
+
+        if logs.len() > retention {
+            for path in &logs[..logs.len() - retention] {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,25 @@
+pub mod plan;
+pub mod command;
+pub mod process;
+pub mod events;
+pub mod subscribers;
+pub mod logs;
+pub mod hooks;
+pub mod files;
+pub mod lock;
+pub mod wineserver;
+
+pub use plan::{LaunchPlan, LaunchFile};
+pub use command::LaunchCommand;
+pub use process::GameProcess;
+pub use logs::LaunchLogs;
+pub use files::GeneratedFiles;
+pub use lock::{PrefixLock, AlreadyRunning};
+pub use wineserver::{WineserverCleanup, WineserverReport};
+
+pub use events::{
+    LaunchStep,
+    LaunchEvent,
+    LaunchEvents,
+    LaunchSubscriber
+};
@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::io::Write;
+use std::process::Command;
+use std::os::unix::process::CommandExt;
+
+use crate::config::schema_blanks::prelude::{
+    WineDrives,
+    AllowedDrives,
+    Hooks
+};
+
+use crate::template::Keywords;
+
+use super::{GameProcess, LaunchLogs, GeneratedFiles};
+use super::wineserver::WineserverCleanup;
+
+use crate::components::overlay::{PrefixOverlay, OverlayMount};
+use crate::components::prefix::set_prefix_runner;
+
+/// File generated by the launcher before running the game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchFile {
+    pub path: PathBuf,
+    pub content: String
+}
+
+/// Everything needed to run the game
+/// 
+/// Plan is built from the game's config without any side effects,
+/// so it can be shown to the user before actually running the game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LaunchPlan {
+    /// Command to run. First item is the binary
+    /// 
+    /// Command is spawned directly, without any shell
+    pub args: Vec<String>,
+
+    /// Environment variables passed to the command
+    pub env: HashMap<String, String>,
+
+    /// Working directory of the command
+    pub current_dir: PathBuf,
+
+    /// Wine prefix used to run the game
+    /// 
+    /// For proton builds this is the compat data folder.
+    /// The prefix is locked and the wine build is recorded in it
+    pub prefix: PathBuf,
+
+    /// Real wine prefix
+    /// 
+    /// Differs from `prefix` for proton builds which store it in the `pfx` folder.
+    /// Drives are mapped in it, and game processes are found by it
+    pub wine_prefix: PathBuf,
+
+    /// Folders which should be mapped to the prefix drives
+    pub drives: HashMap<AllowedDrives, PathBuf>,
+
+    /// Helper files which should be written before running the game
+    pub files: Vec<LaunchFile>,
+
+    /// Storage of the written helper files list
+    /// 
+    /// If set, helper files are removed (or restored if they
+    /// overwrote existing ones) after the game is closed.
+    /// Otherwise they're kept in place
+    pub generated_files: Option<GeneratedFiles>,
+
+    /// Storage of the game output logs
+    /// 
+    /// If not set, output is inherited from the launcher
+    pub logs: Option<LaunchLogs>,
+
+    /// Shared base prefix overlay which should be mounted
+    /// using `mount_overlay` before preparing the prefix
+    pub overlay: Option<PrefixOverlay>,
+
+    /// Overlay is mounted by the sandbox, so only its folders are created
+    pub sandbox_overlay: bool,
+
+    /// Name of the wine build recorded in the prefix before running the game
+    /// 
+    /// Wine updates the prefix when it's run by another build, so it's recorded
+    /// as updated by this one. Not set for the prefixes managed by Steam
+    pub runner: Option<String>,
+
+    /// Wineserver shutdown after the game exit
+    /// 
+    /// If not set, leftover wine processes are kept running
+    pub wineserver: Option<WineserverCleanup>,
+
+    /// Commands executed around the game process
+    pub hooks: Hooks,
+
+    /// Keywords available in the hooks' commands
+    /// 
+    /// Hooks are executed outside of the sandbox,
+    /// so these keywords use real folders
+    pub keywords: Keywords
+}
+
+impl LaunchPlan {
+    #[inline]
+    pub fn new(current_dir: impl Into<PathBuf>, prefix: impl Into<PathBuf>) -> Self {
+        let prefix = prefix.into();
+
+        Self {
+            args: Vec::new(),
+            env: HashMap::new(),
+            current_dir: current_dir.into(),
+            wine_prefix: prefix.clone(),
+            prefix,
+            drives: HashMap::new(),
+            files: Vec::new(),
+            generated_files: None,
+            logs: None,
+            overlay: None,
+            sandbox_overlay: false,
+            runner: None,
+            wineserver: None,
+            hooks: Hooks::default(),
+            keywords: Keywords::default()
+        }
+    }
+
+    #[inline]
+    /// Set environment variable
+    pub fn env(&mut self, key: impl ToString, value: impl ToString) -> &mut Self {
+        self.env.insert(key.to_string(), value.to_string());
+
+        self
+    }
+
+    /// Set environment variables
+    pub fn envs<K: ToString, V: ToString>(&mut self, vars: impl IntoIterator<Item = (K, V)>) -> &mut Self {
+        for (key, value) in vars {
+            self.env.insert(key.to_string(), value.to_string());
+        }
+
+        self
+    }
+
+    #[inline]
+    /// Add helper file which will be written before running the game
+    pub fn file(&mut self, path: impl Into<PathBuf>, content: impl ToString) -> &mut Self {
+        self.files.push(LaunchFile {
+            path: path.into(),
+            content: content.to_string()
+        });
+
+        self
+    }
+
+    /// Get environment variables and the command as a single string
+    /// 
+    /// Values of the environment variables from the logs denylist are redacted
+    pub fn to_command_string(&self) -> String {
+        let params = self.logs.as_ref()
+            .map(|logs| logs.params.clone())
+            .unwrap_or_default();
+
+        let mut env = self.env.iter()
+            .map(|(key, value)| {
+                if params.is_redacted(key) {
+                    format!("{key}=\"<redacted>\"")
+                } else {
+                    format!("{key}=\"{value}\"")
+                }
+            })
+            .collect::<Vec<_>>();
+
+        env.sort();
+
+        format!("{} {}", env.join(" "), crate::shell::join(&self.args))
+    }
+
+    /// Get command which will be spawned by this plan
+    /// 
+    /// Command is spawned as a leader of its own process group
+    /// so all the game processes can be tracked by `GameProcess`
+    pub fn command(&self) -> anyhow::Result<Command> {
+        let Some((binary, args)) = self.args.split_first() else {
+            anyhow::bail!("Launch command is empty");
+        };
+
+        let mut command = Command::new(binary);
+
+        command.args(args)
+            .envs(&self.env)
+            .current_dir(&self.current_dir)
+            .process_group(0);
+
+        Ok(command)
+    }
+
+    /// Write helper files, map prefix drives and record the wine build in the prefix
+    /// 
+    /// Helper files left after the previous launch are cleaned up first.
+    /// Fails if the prefix overlay is used but not mounted
+    pub fn prepare(&self) -> anyhow::Result<()> {
+        if let Some(overlay) = &self.overlay {
+            if !self.sandbox_overlay && !overlay.is_mounted() {
+                anyhow::bail!("Wine prefix overlay is not mounted: {:?}", overlay.merged);
+            }
+        }
+
+        match &self.generated_files {
+            Some(generated) => {
+                if let Err(err) = generated.cleanup() {
+                    tracing::warn!("Failed to clean up helper files of the previous launch: {err}");
+                }
+
+                for file in &self.files {
+                    generated.write(&file.path, &file.content)?;
+                }
+            }
+
+            None => {
+                for file in &self.files {
+                    std::fs::write(&file.path, &file.content)?;
+                }
+            }
+        }
+
+        if !self.drives.is_empty() {
+            // Overlay's changes folder doesn't have it until wine changes drives
+            std::fs::create_dir_all(self.wine_prefix.join("dosdevices"))?;
+        }
+
+        for (drive, path) in &self.drives {
+            WineDrives::map_folder(&self.wine_prefix, *drive, path)?;
+        }
+
+        if let Some(runner) = &self.runner {
+            set_prefix_runner(&self.prefix, runner)?;
+        }
+
+        Ok(())
+    }
+
+    /// Mount the prefix overlay, or create its folders if it's mounted by the sandbox
+    /// 
+    /// Overlay is unmounted when the returned guard is dropped, so it should be kept
+    /// until the game exits. Returns `None` if the overlay is not mounted by the launcher
+    /// 
+    /// ```
+    /// use anime_launcher_sdk::launch::{LaunchPlan, LaunchEvents, PrefixLock};
+    /// use anime_launcher_sdk::components::overlay::PrefixOverlay;
+    /// use anime_launcher_sdk::tools::Tool;
+    /// 
+    /// let path = std::env::temp_dir().join("anime-launcher-sdk-overlay-launch");
+    /// let overlay = PrefixOverlay::new(path.join("base"), path.join("prefix"));
+    /// 
+    /// std::fs::create_dir_all(&overlay.base).unwrap();
+    /// 
+    /// let mut plan = LaunchPlan::new(&path, &overlay.merged);
+    /// 
+    /// plan.args = vec![String::from("true")];
+    /// plan.overlay = Some(overlay.clone());
+    /// 
+    /// // Overlay can be mounted only if FUSE is available
+    /// if Tool::FuseOverlayfs.path().is_some() && std::path::Path::new("/dev/fuse").exists() {
+    ///     let (sender, receiver) = std::sync::mpsc::channel();
+    /// 
+    ///     {
+    ///         // The same way the games are launched
+    ///         let _overlay = plan.mount_overlay().unwrap();
+    ///         let mut lock = PrefixLock::acquire(&plan.prefix).unwrap();
+    /// 
+    ///         assert!(overlay.is_mounted());
+    /// 
+    ///         LaunchEvents::new(sender).watch(&plan, &mut lock).unwrap();
+    ///     }
+    /// 
+    ///     assert!(receiver.iter().count() > 0);
+    ///     assert!(!overlay.is_mounted());
+    /// }
+    /// 
+    /// std::fs::remove_dir_all(path).unwrap();
+    /// ```
+    pub fn mount_overlay(&self) -> anyhow::Result<Option<OverlayMount>> {
+        match &self.overlay {
+            Some(overlay) if self.sandbox_overlay => {
+                overlay.create_folders()?;
+
+                Ok(None)
+            }
+
+            Some(overlay) => overlay.mount().map(Some),
+            None => Ok(None)
+        }
+    }
+
+    /// Remove written helper files, restoring the overwritten ones
+    pub fn cleanup(&self) -> anyhow::Result<()> {
+        match &self.generated_files {
+            Some(generated) => generated.cleanup(),
+            None => Ok(())
+        }
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
+    /// Prepare and spawn the command
+    /// 
+    /// Helper files are cleaned up if the command failed to spawn
+    pub fn execute(&self) -> anyhow::Result<GameProcess> {
+        let process = self.prepare().and_then(|_| self.spawn());
+
+        if process.is_err() {
+            if let Err(err) = self.cleanup() {
+                tracing::warn!("Failed to clean up helper files: {err}");
+            }
+        }
+
+        process
+    }
+
+    fn spawn(&self) -> anyhow::Result<GameProcess> {
+        let command_string = self.to_command_string();
+
+        tracing::info!("Running the game with command: {command_string}");
+
+        let mut command = self.command()?;
+
+        if let Some(logs) = &self.logs {
+            if logs.params.enabled {
+                let (path, mut file) = logs.create()?;
+
+                tracing::info!("Writing game output to {path:?}");
+
+                writeln!(file, "Command: {command_string}")?;
+                writeln!(file, "Working directory: {:?}\n", self.current_dir)?;
+
+                command.stdout(file.try_clone()?)
+                    .stderr(file);
+            }
+        }
+
+        // Wine can start the game in another process group
+        Ok(GameProcess::new(command.spawn()?).with_prefix(&self.wine_prefix))
+    }
+}
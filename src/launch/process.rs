@@ -0,0 +1,201 @@
+use std::path::PathBuf;
+use std::process::{Command, Child, ExitStatus};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::wineserver::get_prefix_processes;
+
+/// Interval between `/proc` scans in `GameProcess::wait`
+const WAIT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Handle of the running game
+/// 
+/// Launch command is spawned as a leader of its own process group.
+/// Every process started by wine inherits this group, so the game
+/// is considered running while any (non-zombie) process of the group is alive,
+/// even if the spawned command itself already exited
+/// 
+/// Wine can start processes in a new process group or session (e.g. using `start`),
+/// so if the wine prefix is set, then the game is also considered running while
+/// any process run with this prefix is alive. Wineserver itself is not tracked
+#[derive(Debug)]
+pub struct GameProcess {
+    child: Mutex<Child>,
+    pgid: u32,
+    prefix: Option<PathBuf>
+}
+
+impl GameProcess {
+    /// Wrap spawned child process
+    /// 
+    /// Child must be spawned as a process group leader (see `LaunchPlan::command`)
+    pub fn new(child: Child) -> Self {
+        Self {
+            pgid: child.id(),
+            child: Mutex::new(child),
+            prefix: None
+        }
+    }
+
+    #[inline]
+    /// Track processes run with the given `WINEPREFIX` too
+    pub fn with_prefix(self, prefix: impl Into<PathBuf>) -> Self {
+        Self {
+            prefix: Some(prefix.into()),
+            ..self
+        }
+    }
+
+    #[inline]
+    /// PID of the spawned command
+    pub fn pid(&self) -> u32 {
+        self.pgid
+    }
+
+    #[inline]
+    /// ID of the tracked process group
+    pub fn pgid(&self) -> u32 {
+        self.pgid
+    }
+
+    /// Get PIDs of all the alive processes of the game's process group,
+    /// and of the processes run with the game's wine prefix
+    pub fn pids(&self) -> Vec<u32> {
+        // Reap spawned command so it won't be listed as a zombie
+        self.exit_status();
+
+        let mut pids = get_group_pids(self.pgid);
+
+        for pid in self.prefix_pids() {
+            if !pids.contains(&pid) {
+                pids.push(pid);
+            }
+        }
+
+        pids
+    }
+
+    /// Get PIDs of the processes run with the game's wine prefix,
+    /// except the wineserver
+    fn prefix_pids(&self) -> Vec<u32> {
+        let Some(prefix) = &self.prefix else {
+            return Vec::new();
+        };
+
+        get_prefix_processes(prefix).into_iter()
+            .filter(|process| !process.name.starts_with("wineserver"))
+            .filter(|process| matches!(read_stat(process.pid), Some(stat) if stat.is_alive()))
+            .map(|process| process.pid)
+            .collect()
+    }
+
+    #[inline]
+    /// Check if any process of the game is still running
+    pub fn is_running(&self) -> bool {
+        !self.pids().is_empty()
+    }
+
+    /// Get exit status of the spawned command, if it exited
+    /// 
+    /// Note that wine usually starts the game and exits right away,
+    /// so use `is_running` to check if the game itself is still running
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        match self.child.lock() {
+            Ok(mut child) => child.try_wait().ok().flatten(),
+            Err(_) => None
+        }
+    }
+
+    /// Block current thread until all the game processes exit
+    /// 
+    /// Returns exit status of the spawned command
+    pub fn wait(&self) -> anyhow::Result<ExitStatus> {
+        while self.is_running() {
+            std::thread::sleep(WAIT_INTERVAL);
+        }
+
+        match self.child.lock() {
+            Ok(mut child) => Ok(child.wait()?),
+            Err(err) => anyhow::bail!("Failed to lock game process: {err}")
+        }
+    }
+
+    /// Send termination signal to all the game processes
+    /// 
+    /// `SIGTERM` is sent by default, and `SIGKILL` if `force` is true
+    #[tracing::instrument(level = "info", skip(self))]
+    pub fn terminate(&self, force: bool) -> anyhow::Result<()> {
+        if !self.is_running() {
+            return Ok(());
+        }
+
+        tracing::info!("Terminating game process group {}", self.pgid);
+
+        let mut command = Command::new("kill");
+
+        command.arg(if force { "-KILL" } else { "-TERM" })
+            .arg("--")
+            .arg(format!("-{}", self.pgid));
+
+        // Processes which left the game's group
+        for pid in self.prefix_pids() {
+            command.arg(pid.to_string());
+        }
+
+        let output = command.output()?;
+
+        // Group could exit between the check and the kill call
+        if !output.status.success() && self.is_running() {
+            anyhow::bail!("Failed to terminate the game: {}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+
+        Ok(())
+    }
+}
+
+/// Process info from `/proc/<pid>/stat`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ProcessStat {
+    pub state: char,
+    pub pgid: u32,
+
+    /// Time the process started after system boot, in clock ticks
+    /// 
+    /// Used to tell the process from another one which got the same PID later
+    pub start_time: u64
+}
+
+impl ProcessStat {
+    #[inline]
+    /// Check if the process is not a zombie
+    pub fn is_alive(&self) -> bool {
+        self.state != 'Z' && self.state != 'X'
+    }
+}
+
+/// Read process info from `/proc/<pid>/stat`
+pub(crate) fn read_stat(pid: u32) -> Option<ProcessStat> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+
+    // Process name can contain spaces and brackets, so we skip until the last ')'
+    // Format: pid (comm) state ppid pgrp ... starttime (22nd field) ...
+    let fields = stat[stat.rfind(')')? + 1..].split_whitespace().collect::<Vec<_>>();
+
+    Some(ProcessStat {
+        state: fields.first()?.chars().next()?,
+        pgid: fields.get(2)?.parse().ok()?,
+        start_time: fields.get(19)?.parse().ok()?
+    })
+}
+
+/// Get PIDs of all the alive processes of the given process group
+pub(crate) fn get_group_pids(pgid: u32) -> Vec<u32> {
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    entries.flatten()
+        .filter_map(|entry| entry.file_name().to_str()?.parse::<u32>().ok())
+        .filter(|pid| matches!(read_stat(*pid), Some(stat) if stat.pgid == pgid && stat.is_alive()))
+        .collect()
+}
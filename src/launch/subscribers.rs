@@ -0,0 +1,204 @@
+#[cfg(feature = "sessions")]
+use std::path::PathBuf;
+
+#[cfg(any(feature = "sessions", feature = "stats"))]
+use std::marker::PhantomData;
+
+#[cfg(feature = "stats")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::events::{LaunchEvent, LaunchStep, LaunchSubscriber};
+use super::hooks;
+
+use crate::config::schema_blanks::prelude::Hooks;
+use crate::template::Keywords;
+
+#[cfg(feature = "discord-rpc")]
+use crate::discord_rpc::{DiscordRpc, RpcUpdates};
+
+#[cfg(feature = "sessions")]
+use crate::sessions::SessionsExt;
+
+#[cfg(feature = "stats")]
+use crate::stats::{StatsExt, PlaySession};
+
+#[cfg(feature = "discord-rpc")]
+/// Update Discord RPC activity while the game is running
+pub struct DiscordRpcSubscriber {
+    rpc: DiscordRpc,
+    connected: bool
+}
+
+#[cfg(feature = "discord-rpc")]
+impl DiscordRpcSubscriber {
+    #[inline]
+    pub fn new(rpc: DiscordRpc) -> Self {
+        Self {
+            rpc,
+            connected: false
+        }
+    }
+}
+
+#[cfg(feature = "discord-rpc")]
+impl LaunchSubscriber for DiscordRpcSubscriber {
+    fn handle(&mut self, event: &LaunchEvent) -> anyhow::Result<()> {
+        match event {
+            LaunchEvent::Spawned { .. } => {
+                self.rpc.update(RpcUpdates::Connect)?;
+
+                self.connected = true;
+            }
+
+            LaunchEvent::Running if self.connected => self.rpc.update(RpcUpdates::Update)?,
+
+            // RPC thread panics if we disconnect without connecting first
+            LaunchEvent::Exited { .. } |
+            LaunchEvent::Failed(_) if self.connected => {
+                self.rpc.update(RpcUpdates::Disconnect)?;
+
+                self.connected = false;
+            }
+
+            _ => ()
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "sessions")]
+/// Apply current game session before running the game
+/// and save it after the game is closed
+pub struct SessionsSubscriber<T: SessionsExt> {
+    prefix: PathBuf,
+    _sessions: PhantomData<fn() -> T>
+}
+
+#[cfg(feature = "sessions")]
+impl<T: SessionsExt> SessionsSubscriber<T> {
+    #[inline]
+    pub fn new(prefix: impl Into<PathBuf>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            _sessions: PhantomData
+        }
+    }
+}
+
+#[cfg(feature = "sessions")]
+impl<T: SessionsExt> LaunchSubscriber for SessionsSubscriber<T> {
+    fn handle(&mut self, event: &LaunchEvent) -> anyhow::Result<()> {
+        match event {
+            LaunchEvent::Preparing(LaunchStep::ApplyingSession) => {
+                if let Some(current) = T::get_current()? {
+                    T::apply(current, &self.prefix)?;
+                }
+            }
+
+            LaunchEvent::Exited { .. } => {
+                if let Some(current) = T::get_current()? {
+                    T::update(current, &self.prefix)?;
+                }
+            }
+
+            _ => ()
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "stats")]
+/// Record game session to the stats file after the game is closed
+pub struct StatsSubscriber<T: StatsExt> {
+    session: PlaySession,
+    _stats: PhantomData<fn() -> T>
+}
+
+#[cfg(feature = "stats")]
+impl<T: StatsExt> StatsSubscriber<T> {
+    #[inline]
+    pub fn new(game: impl ToString, edition: Option<String>, wine: Option<String>) -> Self {
+        Self {
+            session: PlaySession {
+                game: game.to_string(),
+                edition,
+                wine,
+                start: 0,
+                end: 0,
+                exit_code: None
+            },
+            _stats: PhantomData
+        }
+    }
+
+    #[inline]
+    fn timestamp() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_secs())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "stats")]
+impl<T: StatsExt> LaunchSubscriber for StatsSubscriber<T> {
+    fn handle(&mut self, event: &LaunchEvent) -> anyhow::Result<()> {
+        match event {
+            LaunchEvent::Spawned { .. } => self.session.start = Self::timestamp(),
+
+            // Wineserver cleanup can take some time after the game exit
+            LaunchEvent::Exited { code, duration } => {
+                self.session.end = self.session.start + duration.as_secs();
+                self.session.exit_code = *code;
+
+                T::record(&self.session)?;
+            }
+
+            _ => ()
+        }
+
+        Ok(())
+    }
+}
+
+/// Run pre-launch hooks before spawning the game
+/// and post-exit hooks after the game is closed
+pub struct HooksSubscriber {
+    hooks: Hooks,
+    keywords: Keywords
+}
+
+impl HooksSubscriber {
+    #[inline]
+    pub fn new(hooks: Hooks, keywords: Keywords) -> Self {
+        Self {
+            hooks,
+            keywords
+        }
+    }
+}
+
+impl LaunchSubscriber for HooksSubscriber {
+    fn handle(&mut self, event: &LaunchEvent) -> anyhow::Result<()> {
+        match event {
+            LaunchEvent::Preparing(LaunchStep::RunningHooks) => {
+                hooks::run_all(&self.hooks.pre_launch, &self.keywords)?;
+            }
+
+            LaunchEvent::Exited { code, duration } => {
+                let mut keywords = self.keywords.clone();
+
+                keywords.set("exit_code", code.map(|code| code.to_string()).unwrap_or_default())
+                    .set("playtime", duration.as_secs());
+
+                hooks::run_all(&self.hooks.post_exit, &keywords)?;
+            }
+
+            _ => ()
+        }
+
+        Ok(())
+    }
+}
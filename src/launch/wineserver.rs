@@ -0,0 +1,181 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::os::unix::ffi::OsStrExt;
+use std::time::{Duration, Instant};
+
+use wincompatlib::prelude::*;
+
+use crate::components::wine::UnifiedWine;
+use crate::config::schema_blanks::prelude::Wineserver;
+
+/// Interval between wineserver's exit status checks
+const WINESERVER_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Wine process which was running after the game exit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WineProcess {
+    pub pid: u32,
+    pub name: String
+}
+
+/// What was done to stop the wineserver
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WineserverReport {
+    /// Wine processes which were running after the game exit
+    pub leftovers: Vec<WineProcess>,
+
+    /// Wineserver exited by itself before the timeout
+    pub exited: bool,
+
+    /// Wine processes were asked to stop
+    pub stopped: bool,
+
+    /// Wine processes were killed
+    pub killed: bool
+}
+
+/// Wineserver shutdown after the game exit
+/// 
+/// Wineserver running in the sandbox can't be reached from outside of it,
+/// so this shouldn't be used with sandboxed games
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WineserverCleanup {
+    pub wine: UnifiedWine,
+
+    /// Path to the wineserver binary
+    pub wineserver: PathBuf,
+
+    /// Real wine prefix path
+    pub prefix: PathBuf,
+
+    pub params: Wineserver
+}
+
+impl WineserverCleanup {
+    /// Wait for the wineserver to exit and stop it if it didn't
+    ///
+    /// 1. `wineserver -w` is waited for the configured timeout
+    /// 2. Wine processes are asked to stop
+    /// 3. Wine processes are killed if `force` is enabled
+    #[tracing::instrument(level = "info", skip(self), ret)]
+    pub fn run(&self) -> anyhow::Result<WineserverReport> {
+        let mut report = WineserverReport {
+            leftovers: get_prefix_processes(&self.prefix),
+            ..WineserverReport::default()
+        };
+
+        if report.leftovers.is_empty() {
+            report.exited = true;
+
+            return Ok(report);
+        }
+
+        tracing::info!("Waiting for {} wine processes to exit", report.leftovers.len());
+
+        let timeout = Duration::from_secs(self.params.timeout);
+
+        if self.wait(timeout)? {
+            report.exited = true;
+
+            return Ok(report);
+        }
+
+        tracing::info!("Wineserver didn't exit in time. Stopping wine processes");
+
+        self.wine.stop_processes(false)?;
+
+        report.stopped = true;
+
+        if self.wait(timeout)? || !self.params.force {
+            return Ok(report);
+        }
+
+        tracing::warn!("Wine processes didn't stop. Killing them");
+
+        self.wine.stop_processes(true)?;
+
+        // wineboot doesn't kill the wineserver itself
+        Command::new(&self.wineserver)
+            .arg("-k")
+            .env("WINEPREFIX", &self.prefix)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+
+        report.killed = true;
+
+        Ok(report)
+    }
+
+    /// Run `wineserver -w` with timeout
+    ///
+    /// Returns `false` if timeout was reached
+    fn wait(&self, timeout: Duration) -> anyhow::Result<bool> {
+        let mut child = Command::new(&self.wineserver)
+            .arg("-w")
+            .env("WINEPREFIX", &self.prefix)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let started = Instant::now();
+
+        while started.elapsed() < timeout {
+            if child.try_wait()?.is_some() {
+                return Ok(true);
+            }
+
+            std::thread::sleep(WINESERVER_POLL_INTERVAL);
+        }
+
+        child.kill()?;
+        child.wait()?;
+
+        Ok(false)
+    }
+}
+
+/// Get processes which were run with given `WINEPREFIX` variable
+///
+/// Trailing slashes of the variable are ignored.
+/// Only processes of the current user can be found
+pub fn get_prefix_processes(prefix: impl AsRef<Path>) -> Vec<WineProcess> {
+    let mut variable = b"WINEPREFIX=".to_vec();
+
+    variable.extend(trim_slashes(prefix.as_ref().as_os_str().as_bytes()));
+
+    let Ok(entries) = std::fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+
+    let mut processes = entries.flatten()
+        .filter_map(|entry| entry.file_name().to_string_lossy().parse::<u32>().ok())
+        .filter(|pid| *pid != std::process::id())
+        .filter(|pid| {
+            std::fs::read(format!("/proc/{pid}/environ"))
+                .map(|environ| environ.split(|byte| *byte == 0).any(|var| trim_slashes(var) == variable))
+                .unwrap_or_default()
+        })
+        .map(|pid| WineProcess {
+            pid,
+            name: std::fs::read_to_string(format!("/proc/{pid}/comm"))
+                .map(|name| name.trim().to_string())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+
+    processes.sort_by_key(|process| process.pid);
+
+    processes
+}
+
+/// Proton sets `WINEPREFIX` with a trailing slash
+fn trim_slashes(path: &[u8]) -> &[u8] {
+    let mut path = path;
+
+    while path.len() > 1 && path.ends_with(b"/") {
+        path = &path[..path.len() - 1];
+    }
+
+    path
+}
@@ -1,10 +1,12 @@
-use std::process::{Command, Stdio};
-
 pub use anime_game_core;
 pub use wincompatlib;
 
 mod games;
 
+pub mod shell;
+pub mod tools;
+pub mod template;
+
 #[cfg(feature = "genshin")]
 pub use games::genshin;
 
@@ -26,31 +28,32 @@ pub mod config;
 #[cfg(feature = "components")]
 pub mod components;
 
+#[cfg(feature = "game")]
+pub mod launch;
+
 #[cfg(feature = "discord-rpc")]
 pub mod discord_rpc;
 
 #[cfg(feature = "sessions")]
 pub mod sessions;
 
-pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+#[cfg(feature = "stats")]
+pub mod stats;
+
+#[cfg(feature = "diagnostics")]
+pub mod diagnostics;
 
-// TODO: rewrite it to find this binary in PATH instead
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
-/// Check if specified binary is available
+/// Check if specified binary is available in `PATH`
+/// 
+/// Binary is not executed. Use `tools` module to get more info about it
 /// 
 /// ```
 /// assert!(anime_launcher_sdk::is_available("bash"));
 /// ```
-#[allow(unused_must_use)]
+#[inline]
 #[tracing::instrument(level = "trace", ret)]
 pub fn is_available(binary: &str) -> bool {
-    tracing::trace!("Checking package availability");
-
-    let Ok(mut child) = Command::new(binary).stdout(Stdio::null()).stderr(Stdio::null()).spawn() else {
-        return false;
-    };
-
-    child.kill();
-
-    true
+    tools::find_binary(binary).is_some()
 }
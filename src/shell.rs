@@ -0,0 +1,149 @@
+/// Quote string so it will be passed to the shell as a single argument
+/// 
+/// ```
+/// use anime_launcher_sdk::shell::quote;
+/// 
+/// assert_eq!(quote("wine64"), "wine64");
+/// assert_eq!(quote("/path/to/Anime Game"), "'/path/to/Anime Game'");
+/// assert_eq!(quote("it's $HOME"), "'it'\\''s $HOME'");
+/// ```
+pub fn quote(arg: impl AsRef<str>) -> String {
+    let arg = arg.as_ref();
+
+    if arg.is_empty() {
+        return String::from("''");
+    }
+
+    if arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./=:,+@%".contains(c)) {
+        return arg.to_string();
+    }
+
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+/// Quote all the arguments and join them into a single shell command
+pub fn join<T: AsRef<str>>(args: impl IntoIterator<Item = T>) -> String {
+    args.into_iter()
+        .map(quote)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Quote string so it will be passed to the windows batch file as a single argument
+/// 
+/// Percent signs are doubled because batch files expand variables even inside quotes
+/// 
+/// ```
+/// use anime_launcher_sdk::shell::quote_batch;
+/// 
+/// assert_eq!(quote_batch("StarRail.exe"), "StarRail.exe");
+/// assert_eq!(quote_batch("Z:\\path\\to\\Anime Game.exe"), "\"Z:\\path\\to\\Anime Game.exe\"");
+/// 
+/// assert_eq!(quote_batch("100%"), "\"100%%\"");
+/// assert_eq!(quote_batch("%APPDATA% dir"), "\"%%APPDATA%% dir\"");
+/// ```
+pub fn quote_batch(arg: impl AsRef<str>) -> String {
+    let arg = arg.as_ref();
+
+    if !arg.is_empty() && !arg.chars().any(|c| c.is_whitespace() || "\"&|<>^%,;=()".contains(c)) {
+        return arg.to_string();
+    }
+
+    format!("\"{}\"", arg.replace('"', "\"\"").replace('%', "%%"))
+}
+
+/// Split shell command into arguments
+/// 
+/// Supports single and double quotes and backslash escaping.
+/// Doesn't expand any variables
+/// 
+/// ```
+/// use anime_launcher_sdk::shell::split;
+/// 
+/// assert_eq!(split("python3 '%build%/proton' waitforexitandrun").unwrap(), vec![
+///     "python3",
+///     "%build%/proton",
+///     "waitforexitandrun"
+/// ]);
+/// 
+/// assert!(split("echo 'unterminated").is_err());
+/// ```
+pub fn split(command: impl AsRef<str>) -> anyhow::Result<Vec<String>> {
+    let mut args = Vec::new();
+
+    let mut current = String::new();
+    let mut has_arg = false;
+
+    let mut chars = command.as_ref().chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                has_arg = true;
+
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+
+                        None => anyhow::bail!("Unterminated single quote in command: {}", command.as_ref())
+                    }
+                }
+            }
+
+            '"' => {
+                has_arg = true;
+
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+
+                            None => anyhow::bail!("Unterminated double quote in command: {}", command.as_ref())
+                        }
+
+                        Some(c) => current.push(c),
+
+                        None => anyhow::bail!("Unterminated double quote in command: {}", command.as_ref())
+                    }
+                }
+            }
+
+            '\\' => {
+                has_arg = true;
+
+                match chars.next() {
+                    Some(c) => current.push(c),
+                    None => anyhow::bail!("Trailing backslash in command: {}", command.as_ref())
+                }
+            }
+
+            c if c.is_whitespace() => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+
+                    has_arg = false;
+                }
+            }
+
+            c => {
+                has_arg = true;
+
+                current.push(c);
+            }
+        }
+    }
+
+    if has_arg {
+        args.push(current);
+    }
+
+    Ok(args)
+}
@@ -0,0 +1,132 @@
+use std::path::PathBuf;
+use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Serialize, Deserialize};
+
+/// Record of a single game session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaySession {
+    pub game: String,
+    pub edition: Option<String>,
+
+    /// Name of the wine build used to run the game
+    pub wine: Option<String>,
+
+    /// UNIX timestamp of the game start, in seconds
+    pub start: u64,
+
+    /// UNIX timestamp of the game exit, in seconds
+    pub end: u64,
+
+    pub exit_code: Option<i32>
+}
+
+impl PlaySession {
+    #[inline]
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs(self.end.saturating_sub(self.start))
+    }
+
+    #[inline]
+    pub fn started_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.start)
+    }
+
+    #[inline]
+    pub fn ended_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.end)
+    }
+}
+
+pub trait StatsExt {
+    /// Get path to the stats file
+    /// 
+    /// Stats are stored in JSON Lines format, one session per line
+    fn stats_file() -> anyhow::Result<PathBuf>;
+
+    /// Get all the recorded sessions, from oldest to newest
+    /// 
+    /// Returns empty list if the stats file doesn't exist.
+    /// Malformed lines are skipped
+    fn get_sessions() -> anyhow::Result<Vec<PlaySession>> {
+        let path = Self::stats_file()?;
+
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let sessions = std::fs::read_to_string(path)?
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .filter_map(|(i, line)| {
+                match serde_json::from_str(line) {
+                    Ok(session) => Some(session),
+                    Err(err) => {
+                        tracing::warn!("Skipping malformed stats record at line {}: {err}", i + 1);
+
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        Ok(sessions)
+    }
+
+    /// Get recorded sessions of the given edition
+    /// 
+    /// `None` means all the editions
+    fn get_edition_sessions(edition: Option<&str>) -> anyhow::Result<Vec<PlaySession>> {
+        Ok(Self::get_sessions()?
+            .into_iter()
+            .filter(|session| edition.is_none() || session.edition.as_deref() == edition)
+            .collect())
+    }
+
+    /// Append session to the stats file
+    fn record(session: &PlaySession) -> anyhow::Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(Self::stats_file()?)?;
+
+        // Write the whole line at once so it can't be interleaved
+        file.write_all(format!("{}\n", serde_json::to_string(session)?).as_bytes())?;
+
+        Ok(())
+    }
+
+    /// Get total playtime of the given edition (`None` for all editions)
+    fn total_playtime(edition: Option<&str>) -> anyhow::Result<Duration> {
+        Ok(Self::get_edition_sessions(edition)?
+            .iter()
+            .map(PlaySession::duration)
+            .sum())
+    }
+
+    /// Get the latest session of the given edition (`None` for all editions)
+    fn last_played(edition: Option<&str>) -> anyhow::Result<Option<PlaySession>> {
+        Ok(Self::get_edition_sessions(edition)?
+            .into_iter()
+            .max_by_key(|session| session.end))
+    }
+
+    /// Get average session length of the given edition (`None` for all editions)
+    /// 
+    /// Returns `None` if there are no recorded sessions
+    fn average_session(edition: Option<&str>) -> anyhow::Result<Option<Duration>> {
+        let sessions = Self::get_edition_sessions(edition)?;
+
+        if sessions.is_empty() {
+            return Ok(None);
+        }
+
+        let total = sessions.iter()
+            .map(PlaySession::duration)
+            .sum::<Duration>();
+
+        Ok(Some(total / sessions.len() as u32))
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::shell;
+
+/// How keyword values are inserted into the template
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Quoting {
+    /// Insert values as is
+    Raw,
+
+    /// Quote values as POSIX shell arguments
+    Shell,
+
+    /// Values of environment variables are passed to the process directly,
+    /// so they don't need any quoting
+    Env,
+
+    /// Quote values as windows batch file arguments
+    Batch
+}
+
+impl Quoting {
+    pub fn quote(&self, value: &str) -> String {
+        match self {
+            Self::Raw | Self::Env => value.to_string(),
+
+            Self::Shell => shell::quote(value),
+            Self::Batch => shell::quote_batch(value)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// Template contains `%keyword%` which is not defined
+    UnknownKeyword(String)
+}
+
+impl std::fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnknownKeyword(keyword) => write!(f, "Unknown keyword: %{keyword}%")
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Value<'a> {
+    value: &'a str,
+    raw: bool
+}
+
+/// Set of `%keyword%` values used in templates
+/// 
+/// - Keywords consist of ASCII letters, digits and underscores
+/// - Unknown keywords are errors
+/// - `%%` is replaced by a single `%`
+/// - Other `%` chars are left as is
+/// 
+/// ```
+/// use anime_launcher_sdk::template::{Keywords, Quoting};
+/// 
+/// let mut keywords = Keywords::new();
+/// 
+/// keywords.set("prefix", "/path/to/my prefix");
+/// 
+/// assert_eq!(keywords.render("ls %prefix%", Quoting::Shell).unwrap(), "ls '/path/to/my prefix'");
+/// assert_eq!(keywords.render("%prefix%/drive_c", Quoting::Env).unwrap(), "/path/to/my prefix/drive_c");
+/// assert_eq!(keywords.render("echo 100%% %", Quoting::Shell).unwrap(), "echo 100% %");
+/// 
+/// assert!(keywords.render("ls %unknown%", Quoting::Shell).is_err());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Keywords {
+    values: HashMap<String, (String, bool)>
+}
+
+impl Keywords {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get keywords for the standard folders
+    /// 
+    /// - `%build%` - path to wine build
+    /// - `%prefix%` - path to wine prefix
+    /// - `%game%` - path to the game
+    /// - `%temp%` - path to temp folder specified in config file
+    /// - `%launcher%` - path to launcher folder
+    /// 
+    /// ```
+    /// use anime_launcher_sdk::template::{Keywords, Quoting};
+    /// 
+    /// let keywords = Keywords::with_folders("/wine", "/prefix", "/game", "/temp", "/launcher");
+    /// 
+    /// assert_eq!(keywords.render("%build%", Quoting::Raw).unwrap(), "/wine");
+    /// assert_eq!(keywords.render("%prefix%", Quoting::Raw).unwrap(), "/prefix");
+    /// assert_eq!(keywords.render("%game%", Quoting::Raw).unwrap(), "/game");
+    /// assert_eq!(keywords.render("%temp%", Quoting::Raw).unwrap(), "/temp");
+    /// assert_eq!(keywords.render("%launcher%", Quoting::Raw).unwrap(), "/launcher");
+    /// ```
+    pub fn with_folders(
+        wine: impl AsRef<Path>,
+        prefix: impl AsRef<Path>,
+        game: impl AsRef<Path>,
+        temp: impl AsRef<Path>,
+        launcher: impl AsRef<Path>
+    ) -> Self {
+        let mut keywords = Self::new();
+
+        keywords.set_path("build", wine)
+            .set_path("prefix", prefix)
+            .set_path("game", game)
+            .set_path("temp", temp)
+            .set_path("launcher", launcher);
+
+        keywords
+    }
+
+    #[inline]
+    /// Set keyword value. It will be quoted according to the template context
+    pub fn set(&mut self, keyword: impl ToString, value: impl ToString) -> &mut Self {
+        self.values.insert(keyword.to_string(), (value.to_string(), false));
+
+        self
+    }
+
+    #[inline]
+    /// Set keyword value from the path
+    /// 
+    /// Non UTF-8 chars are replaced by `U+FFFD`
+    pub fn set_path(&mut self, keyword: impl ToString, path: impl AsRef<Path>) -> &mut Self {
+        self.set(keyword, path.as_ref().to_string_lossy())
+    }
+
+    #[inline]
+    /// Set keyword value which is never quoted
+    /// 
+    /// Use it for the values which are already quoted, like shell commands
+    pub fn set_raw(&mut self, keyword: impl ToString, value: impl ToString) -> &mut Self {
+        self.values.insert(keyword.to_string(), (value.to_string(), true));
+
+        self
+    }
+
+    #[inline]
+    /// Add all the keywords from another set, replacing existing ones
+    pub fn merge(&mut self, keywords: &Keywords) -> &mut Self {
+        self.values.extend(keywords.values.clone());
+
+        self
+    }
+
+    #[inline]
+    /// Get keyword value
+    pub fn get(&self, keyword: impl AsRef<str>) -> Option<&str> {
+        self.values.get(keyword.as_ref()).map(|(value, _)| value.as_str())
+    }
+
+    fn get_value(&self, keyword: &str) -> Option<Value<'_>> {
+        self.values.get(keyword).map(|(value, raw)| Value {
+            value,
+            raw: *raw
+        })
+    }
+
+    /// Replace keywords in the template, quoting them for the given context
+    pub fn render(&self, template: impl AsRef<str>, quoting: Quoting) -> Result<String, TemplateError> {
+        let template = template.as_ref();
+
+        let mut result = String::with_capacity(template.len());
+        let mut rest = template;
+
+        while let Some(pos) = rest.find('%') {
+            result.push_str(&rest[..pos]);
+
+            rest = &rest[pos + 1..];
+
+            // Escaped %
+            if let Some(tail) = rest.strip_prefix('%') {
+                result.push('%');
+
+                rest = tail;
+
+                continue;
+            }
+
+            let keyword = rest.find('%')
+                .map(|end| &rest[..end])
+                .filter(|keyword| !keyword.is_empty() && keyword.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'));
+
+            match keyword {
+                Some(keyword) => {
+                    let Some(value) = self.get_value(keyword) else {
+                        return Err(TemplateError::UnknownKeyword(keyword.to_string()));
+                    };
+
+                    if value.raw {
+                        result.push_str(value.value);
+                    } else {
+                        result.push_str(&quoting.quote(value.value));
+                    }
+
+                    rest = &rest[keyword.len() + 1..];
+                }
+
+                // Not a keyword
+                None => result.push('%')
+            }
+        }
+
+        result.push_str(rest);
+
+        Ok(result)
+    }
+}
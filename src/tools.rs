@@ -0,0 +1,151 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::os::unix::fs::PermissionsExt;
+
+/// External tools used by the launcher
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Tool {
+    Gamescope,
+    MangoHud,
+    GameMode,
+    Bwrap,
+
+    /// Needed to run Proton builds
+    Python3,
+
+    /// Needed to mount wine prefix overlays
+    FuseOverlayfs
+}
+
+impl Tool {
+    pub const ALL: &'static [Self] = &[
+        Self::Gamescope,
+        Self::MangoHud,
+        Self::GameMode,
+        Self::Bwrap,
+        Self::Python3,
+        Self::FuseOverlayfs
+    ];
+
+    /// Name of the binary used by the launcher
+    pub fn binary(&self) -> &'static str {
+        match self {
+            Self::Gamescope     => "gamescope",
+            Self::MangoHud      => "mangohud",
+            Self::GameMode      => "gamemoderun",
+            Self::Bwrap         => "bwrap",
+            Self::Python3       => "python3",
+            Self::FuseOverlayfs => "fuse-overlayfs"
+        }
+    }
+
+    /// Command used to get the tool's version
+    fn version_command(&self) -> (&'static str, &'static [&'static str]) {
+        match self {
+            // gamescope doesn't have --version, so parsing --help instead
+            Self::Gamescope => ("gamescope", &["--help"]),
+
+            // gamemoderun is a script which runs given command
+            Self::GameMode => ("gamemoded", &["--version"]),
+
+            _ => (self.binary(), &["--version"])
+        }
+    }
+
+    #[inline]
+    /// Find the tool's binary in `PATH`
+    pub fn path(&self) -> Option<PathBuf> {
+        find_binary(self.binary())
+    }
+
+    #[inline]
+    /// Check if the tool's binary is available in `PATH`
+    pub fn is_available(&self) -> bool {
+        self.path().is_some()
+    }
+
+    /// Get the tool's version
+    /// 
+    /// This will run the tool once and cache its result
+    pub fn version(&self) -> Option<String> {
+        let (binary, args) = self.version_command();
+
+        let output = output(binary, args)?;
+
+        // gamescope's help contains numbers which look like versions,
+        // so we search only after the "version" word
+        if *self == Self::Gamescope {
+            return output.find("version ").and_then(|pos| parse_version(&output[pos..]));
+        }
+
+        parse_version(&output)
+    }
+}
+
+/// Find binary in `PATH` without executing it
+/// 
+/// Paths with slashes are checked directly. Only found binaries are cached,
+/// so tools installed while the launcher is running are found by the next call
+/// 
+/// ```
+/// use anime_launcher_sdk::tools::find_binary;
+/// 
+/// assert!(find_binary("bash").is_some());
+/// assert!(find_binary("definitely-not-existing-binary").is_none());
+/// ```
+#[cached::proc_macro::cached(key = "String", convert = r##"{ binary.to_string() }"##, option)]
+pub fn find_binary(binary: &str) -> Option<PathBuf> {
+    if binary.contains('/') {
+        let path = PathBuf::from(binary);
+
+        return is_executable(&path).then_some(path);
+    }
+
+    let paths = std::env::var_os("PATH")?;
+
+    std::env::split_paths(&paths)
+        .map(|folder| folder.join(binary))
+        .find(|path| is_executable(path))
+}
+
+/// Run binary from `PATH` and get its stdout and stderr
+/// 
+/// Returns `None` if the binary is not available or couldn't be executed.
+/// Successful results are cached, so every command is executed only once
+#[cached::proc_macro::cached(
+    key = "(String, Vec<String>)",
+    convert = r##"{ (binary.to_string(), args.iter().map(|arg| arg.to_string()).collect()) }"##,
+    option
+)]
+pub fn output(binary: &str, args: &[&str]) -> Option<String> {
+    let path = find_binary(binary)?;
+
+    tracing::debug!("Running {:?} with args {:?}", path, args);
+
+    let output = Command::new(path).args(args).output().ok()?;
+
+    Some(format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr)))
+}
+
+/// Find the first version-like word in the text
+/// 
+/// ```
+/// use anime_launcher_sdk::tools::parse_version;
+/// 
+/// assert_eq!(parse_version("bubblewrap 0.8.0"), Some(String::from("0.8.0")));
+/// assert_eq!(parse_version("gamemoded version v1.7"), Some(String::from("1.7")));
+/// assert_eq!(parse_version("Python 3.11.4\n"), Some(String::from("3.11.4")));
+/// assert_eq!(parse_version("usage: gamescope [options...]"), None);
+/// ```
+pub fn parse_version(text: &str) -> Option<String> {
+    text.split_whitespace()
+        .map(|word| word.trim_start_matches('v').trim_end_matches([',', ')', ':']))
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()) && word.contains('.'))
+        .map(String::from)
+}
+
+fn is_executable(path: &Path) -> bool {
+    path.metadata()
+        .map(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or_default()
+}